@@ -0,0 +1,110 @@
+use std::env;
+
+use zbus::{names::WellKnownName, zvariant::ObjectPath};
+
+/// Default well-known name to request on the bus
+pub const DEFAULT_BUS_NAME: &str = "org.shadowblip.Gamescope";
+/// Default base path that all objects are served under
+pub const DEFAULT_BASE_PATH: &str = "/org/shadowblip/Gamescope";
+
+const USAGE: &str = "Usage: gamescope-dbus [OPTIONS]
+
+Options:
+  --bus-name <NAME>   Well-known DBus name to request [default: org.shadowblip.Gamescope]
+  --base-path <PATH>  Base DBus object path to serve objects under [default: /org/shadowblip/Gamescope]
+  --system-bus        Connect to the system bus instead of the session bus
+  --runtime-dir <DIR> Directory to discover gamescope Wayland sockets in [default: $XDG_RUNTIME_DIR]
+  -h, --help          Print help
+  -V, --version       Print version";
+
+/// Command line arguments
+#[derive(Debug, Clone)]
+pub struct Args {
+    /// Well-known DBus name to request (E.g. "org.shadowblip.Gamescope")
+    pub bus_name: String,
+    /// Base DBus path that all objects are served under (E.g. "/org/shadowblip/Gamescope")
+    pub base_path: String,
+    /// Whether to connect to the system bus instead of the session bus
+    pub system_bus: bool,
+    /// Directory to discover gamescope Wayland sockets in, overriding XDG_RUNTIME_DIR
+    pub runtime_dir: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            bus_name: DEFAULT_BUS_NAME.to_string(),
+            base_path: DEFAULT_BASE_PATH.to_string(),
+            system_bus: env::var("GAMESCOPE_DBUS_BUS")
+                .map(|bus| bus == "system")
+                .unwrap_or_default(),
+            runtime_dir: None,
+        }
+    }
+}
+
+impl Args {
+    /// Parses the arguments the process was started with. Prints usage and
+    /// exits if the arguments are invalid or help was requested.
+    pub fn parse() -> Args {
+        match Self::try_parse(env::args().skip(1)) {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("error: {err}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Parses and validates the given arguments
+    fn try_parse(mut argv: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut args = Args::default();
+        while let Some(arg) = argv.next() {
+            // Support both "--flag value" and "--flag=value"
+            let (flag, inline_value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| argv.next())
+                    .ok_or(format!("a value is required for '{flag}'"))
+            };
+
+            match flag.as_str() {
+                "--bus-name" => args.bus_name = value()?,
+                "--base-path" => args.base_path = value()?,
+                "--system-bus" => args.system_bus = true,
+                "--runtime-dir" => args.runtime_dir = Some(value()?),
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                "-V" | "--version" => {
+                    println!("gamescope-dbus {}", env!("CARGO_PKG_VERSION"));
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unexpected argument '{flag}'")),
+            }
+        }
+        args.validate()?;
+
+        Ok(args)
+    }
+
+    /// Validates that the bus name and base path are well-formed
+    fn validate(&self) -> Result<(), String> {
+        WellKnownName::try_from(self.bus_name.as_str())
+            .map_err(|err| format!("invalid bus name '{}': {err}", self.bus_name))?;
+        ObjectPath::try_from(self.base_path.as_str())
+            .map_err(|err| format!("invalid base path '{}': {err}", self.base_path))?;
+        if self.base_path == "/" {
+            return Err("invalid base path '/': objects cannot be served at the root".into());
+        }
+
+        Ok(())
+    }
+}
@@ -1,22 +1,45 @@
 use std::{
     collections::{HashMap, HashSet},
     error::Error,
-    time::Duration,
+    path::Path,
+    time::{Duration, Instant},
 };
 use tokio::{
     fs,
     sync::{broadcast, mpsc},
 };
-use zbus::{fdo, zvariant::ObjectPath, Connection};
+use zbus::{
+    fdo,
+    names::InterfaceName,
+    zvariant::{ObjectPath, Value},
+    Connection, SignalContext,
+};
 use zbus_macros::dbus_interface;
 
 use crate::{
+    logger,
     utils::{get_run_user_dir, is_gamescope_socket_file},
     watcher::{self, WatchEvent},
 };
 
 use super::{wayland, xwayland};
 
+/// Amount of time to wait after the last filesystem event for a wayland socket
+/// before acting on it. Starting a wayland manager too soon after gamescope
+/// restarts can fail with a broken IO connection.
+const WAYLAND_EVENT_SETTLE_TIME: Duration = Duration::from_secs(1);
+
+/// Delays before each attempt to discover a new XWayland display. The X
+/// display is often not discoverable right after its socket is created,
+/// especially on slower hardware.
+const XWAYLAND_DISCOVERY_DELAYS: [Duration; 5] = [
+    Duration::from_millis(500),
+    Duration::from_millis(750),
+    Duration::from_millis(1000),
+    Duration::from_millis(1500),
+    Duration::from_millis(2000),
+];
+
 #[derive(Debug, Copy, Clone)]
 pub enum WatchType {
     X11,
@@ -39,20 +62,169 @@ pub enum Command {
     XWaylandRemoved {
         name: String,
     },
-    WaylandAdded {
+    /// Sent once no filesystem events have been seen for a wayland socket
+    /// for [WAYLAND_EVENT_SETTLE_TIME]
+    WaylandEventsSettled {
         path: String,
+        generation: u64,
     },
-    WaylandRemoved {
+    /// Sent by a discovery task started with
+    /// [Manager::start_xwayland_discovery] with the displays it found
+    XWaylandsDiscovered {
+        displays: Vec<String>,
+        generation: u64,
+    },
+    WaylandStatus {
+        sender: mpsc::Sender<Vec<(String, bool)>>,
+    },
+    RemoveObject {
         path: String,
+        sender: mpsc::Sender<Result<(), String>>,
+    },
+    XWaylandPaths {
+        sender: mpsc::Sender<Vec<String>>,
+    },
+    WaylandPaths {
+        sender: mpsc::Sender<Vec<String>>,
+    },
+    AppUsage {
+        sender: mpsc::Sender<Vec<(u32, u32, u64)>>,
+    },
+    WaylandSockets {
+        sender: mpsc::Sender<Vec<(String, String)>>,
+    },
+    PrimaryXWaylandPath {
+        sender: mpsc::Sender<String>,
+    },
+    PrimaryXWaylandName {
+        socket_name: String,
+        sender: mpsc::Sender<Option<String>>,
+    },
+    XWaylands {
+        sender: mpsc::Sender<Vec<(String, String, bool)>>,
     },
+    WaylandManager {
+        socket_name: Option<String>,
+        sender: mpsc::Sender<Option<wayland::manager::WaylandManager>>,
+    },
+    Shutdown {
+        sender: mpsc::Sender<()>,
+    },
+    Heartbeat,
+}
+
+/// Generation of the latest filesystem event for each path whose events have
+/// not settled yet. Used to act only on the last of a burst of events.
+#[derive(Debug, Default)]
+struct EventGenerations(HashMap<String, u64>);
+
+impl EventGenerations {
+    /// Records a new event for the given path and returns its generation
+    fn record(&mut self, path: &str) -> u64 {
+        let generation = self.0.entry(path.to_string()).or_default();
+        *generation += 1;
+        *generation
+    }
+
+    /// Returns whether the given generation is the latest for the path. If
+    /// it is, the events for the path have settled and it is forgotten.
+    fn settle(&mut self, path: &str, generation: u64) -> bool {
+        if self.0.get(path) != Some(&generation) {
+            return false;
+        }
+        self.0.remove(path);
+        true
+    }
+}
+
+/// Change made by [Manager::sync_xwaylands] to bring the managed xwayland
+/// instances in line with the discovered displays
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum XWaylandChange {
+    /// Add the interfaces of a newly discovered display at the given path
+    Add {
+        name: String,
+        path: String,
+        primary: bool,
+    },
+    /// Remove the interfaces of a display that no longer exists
+    Remove { name: String, path: String },
+    /// Set the primary path and emit the PrimaryXWaylandPath change
+    PrimaryChanged { path: Option<String> },
+}
+
+/// Returns the changes needed to go from the managed xwayland instances (a
+/// map of display name to DBus path) with the given primary path to the
+/// discovered displays, each along with whether it is a primary instance.
+/// Primacy is only consulted for displays that are not managed yet.
+///
+/// New instances are added before old ones are removed so that a primary
+/// instance is available on the bus for as long as possible when gamescope
+/// restarts, and the primary change comes last, once the new primary is
+/// ready and the old one is gone.
+fn plan_xwayland_sync(
+    base_path: &str,
+    managed: &HashMap<String, String>,
+    primary_path: Option<&str>,
+    discovered: &[(String, bool)],
+) -> Vec<XWaylandChange> {
+    let mut changes = Vec::new();
+    let mut used_paths: HashSet<String> = managed.values().cloned().collect();
+    let mut new_primary_path = primary_path.map(str::to_string);
+
+    for (name, primary) in discovered {
+        if managed.contains_key(name) {
+            continue;
+        }
+        let path = (0..)
+            .map(|i| format!("{base_path}/XWayland{i}"))
+            .find(|path| !used_paths.contains(path))
+            .unwrap_or_default();
+        used_paths.insert(path.clone());
+        if *primary {
+            new_primary_path = Some(path.clone());
+        }
+        changes.push(XWaylandChange::Add {
+            name: name.clone(),
+            path,
+            primary: *primary,
+        });
+    }
+
+    let mut removed: Vec<(&String, &String)> = managed
+        .iter()
+        .filter(|(name, _)| !discovered.iter().any(|(found, _)| found == *name))
+        .collect();
+    removed.sort();
+    for (name, path) in removed {
+        if new_primary_path.as_ref() == Some(path) {
+            new_primary_path = None;
+        }
+        changes.push(XWaylandChange::Remove {
+            name: name.clone(),
+            path: path.clone(),
+        });
+    }
+
+    if new_primary_path.as_deref() != primary_path {
+        changes.push(XWaylandChange::PrimaryChanged {
+            path: new_primary_path,
+        });
+    }
+
+    changes
 }
 
 /// Gamescope Manager instance
 pub struct Manager {
     /// Connection to the DBus bus
     dbus: Connection,
+    /// Base DBus path that all objects are served under (E.g. "/org/shadowblip/Gamescope")
+    base_path: String,
     /// Mapping of XWayland names (":0", ":1") to DBus path ("/org/shadowblip/Gamescope/XWayland0")
     xwaylands: HashMap<String, String>,
+    /// DBus path of the primary XWayland instance
+    primary_path: Option<String>,
     /// List of existing wayland sockets
     waylands: HashSet<String>,
     /// The transmit side of the [rx] channel used to send [Command] messages.
@@ -62,50 +234,94 @@ pub struct Manager {
     /// The receive side of the channel used to listen for [Command] messages
     /// from other objects.
     rx: mpsc::Receiver<Command>,
+    /// Time the manager was created, used to report uptime
+    started: Instant,
+    /// Generation of the latest filesystem event for each wayland socket path
+    /// whose events have not settled yet
+    wayland_events: EventGenerations,
+    /// Generation of the latest XWayland discovery task that was started
+    xwayland_discovery: u64,
+    /// Generation of the latest XWayland discovery result that was applied
+    xwayland_discovery_applied: u64,
 }
 
 impl Manager {
     /// Returns a new instance of the XWayland DBus interface. Will error if
     /// it cannot establish a connection.
-    pub fn new(conn: Connection) -> Manager {
+    pub fn new(conn: Connection, base_path: String) -> Manager {
         let (tx, rx) = mpsc::channel(32);
         Manager {
             dbus: conn,
+            base_path,
             tx,
             rx,
             xwaylands: HashMap::new(),
+            primary_path: None,
             waylands: HashSet::new(),
+            started: Instant::now(),
+            wayland_events: EventGenerations::default(),
+            xwayland_discovery: 0,
+            xwayland_discovery_applied: 0,
         }
     }
 
-    /// Starts the wayland manager and adds its dbus interface
-    pub async fn start_wayland_manager(&self, path: String) -> Result<(), Box<dyn Error>> {
+    /// Returns a clone of the transmit side of the [Command] channel, so other
+    /// objects can communicate with the manager.
+    pub fn tx(&self) -> mpsc::Sender<Command> {
+        self.tx.clone()
+    }
+
+    /// Returns the DBus path of the Manager interface
+    pub fn manager_path(&self) -> String {
+        format!("{}/Manager", self.base_path)
+    }
+
+    /// Returns the DBus path beneath the given base path for the wayland
+    /// manager of the given socket path
+    fn wayland_dbus_path(base_path: &str, path: &str) -> Result<String, Box<dyn Error>> {
         let id = path
             .split('-')
             .last()
             .ok_or("Wrong id found in wayland gamescope socket file name")?;
-        let dbus_path = format!("/org/shadowblip/Gamescope/Wayland{}", id);
-        let interface =
-            wayland::dbus::DBusInterface::new(dbus_path.clone(), self.dbus.clone(), path).await?;
+        Ok(format!("{}/Wayland{}", base_path, id))
+    }
+
+    /// Starts the wayland manager and adds its dbus interface
+    pub async fn start_wayland_manager(&self, path: String) -> Result<(), Box<dyn Error>> {
+        let dbus_path = Self::wayland_dbus_path(&self.base_path, &path)?;
+        let interface = wayland::dbus::DBusInterface::new(
+            dbus_path.clone(),
+            self.dbus.clone(),
+            self.tx.clone(),
+            path,
+        )
+        .await?;
+        let input_method =
+            wayland::input_method::DBusInterface::new(dbus_path.clone(), interface.wayland());
         self.dbus
             .object_server()
             .at(dbus_path.clone(), interface)
             .await?;
+        self.dbus
+            .object_server()
+            .at(dbus_path.clone(), input_method)
+            .await?;
         log::info!("Initialized wayland manager at path:{dbus_path}");
         Ok(())
     }
 
     /// Removes the wayland manager and its dbus interface
     pub async fn remove_wayland_manager(&self, path: String) -> Result<(), Box<dyn Error>> {
-        let id = path
-            .split('-')
-            .last()
-            .ok_or("Wrong id found in wayland gamescope socket file name")?;
-        let dbus_path = format!("/org/shadowblip/Gamescope/Wayland{}", id);
+        let dbus_path = Self::wayland_dbus_path(&self.base_path, &path)?;
         self.dbus
             .object_server()
             .remove::<wayland::dbus::DBusInterface, String>(dbus_path.clone())
             .await?;
+        let _ = self
+            .dbus
+            .object_server()
+            .remove::<wayland::input_method::DBusInterface, String>(dbus_path.clone())
+            .await;
         log::info!("Removed wayland manager at path:{dbus_path}");
         Ok(())
     }
@@ -121,6 +337,7 @@ impl Manager {
         }
 
         self.waylands.insert(path);
+        self.emit_properties_invalidated(&["WaylandSockets"]).await;
     }
 
     async fn remove_wayland_manager_for_path(&mut self, path: String) {
@@ -128,7 +345,372 @@ impl Manager {
             log::error!("Error removing wayland manager at path:{path}, err:{err:?}");
         }
 
-        self.waylands.remove(&path);
+        if self.waylands.remove(&path) {
+            self.emit_properties_invalidated(&["WaylandSockets"]).await;
+        }
+    }
+
+    /// Returns the display name (E.g. ":1") of the primary xwayland instance
+    /// of the gamescope serving the wayland socket with the given name (E.g.
+    /// "gamescope-0"). If the socket of no primary instance matches but only
+    /// one primary instance is managed, that instance is returned.
+    async fn primary_xwayland_name(&self, socket_name: &str) -> Option<String> {
+        let mut primaries = Vec::new();
+        for (name, path) in self.xwaylands.iter() {
+            let is_primary = self
+                .dbus
+                .object_server()
+                .interface::<_, xwayland::DBusInterfacePrimary>(path.clone())
+                .await
+                .is_ok();
+            if is_primary {
+                primaries.push(name.clone());
+            }
+        }
+
+        // Reading the environment of each Xwayland server touches procfs, so
+        // keep it off the executor
+        let candidates = primaries.clone();
+        let socket_name = socket_name.to_string();
+        let matching = tokio::task::spawn_blocking(move || {
+            candidates.into_iter().find(|name| {
+                xwayland::get_wayland_display(name).as_deref() == Some(socket_name.as_str())
+            })
+        })
+        .await
+        .ok()
+        .flatten();
+
+        match matching {
+            Some(name) => Some(name),
+            None if primaries.len() == 1 => primaries.pop(),
+            None => None,
+        }
+    }
+
+    /// Returns the socket path of each managed wayland instance along with
+    /// whether or not the gamescope_control global was bound on it.
+    async fn wayland_status(&self) -> Vec<(String, bool)> {
+        let mut status = Vec::new();
+        for path in self.waylands.iter() {
+            let Ok(dbus_path) = Self::wayland_dbus_path(&self.base_path, path) else {
+                status.push((path.clone(), false));
+                continue;
+            };
+            let control_bound = match self
+                .dbus
+                .object_server()
+                .interface::<_, wayland::dbus::DBusInterface>(dbus_path)
+                .await
+            {
+                Ok(iface_ref) => iface_ref.get().await.control_bound(),
+                Err(_) => false,
+            };
+            status.push((path.clone(), control_bound));
+        }
+        status
+    }
+
+    /// Emits the [DBusInterface] XWaylandAdded or XWaylandRemoved signal for
+    /// the given xwayland DBus path
+    async fn emit_xwayland_signal(&self, path: &str, added: bool) {
+        let Ok(iface_ref) = self
+            .dbus
+            .object_server()
+            .interface::<_, DBusInterface>(self.manager_path())
+            .await
+        else {
+            log::debug!("Manager interface not served yet. Not signaling for {path}");
+            return;
+        };
+        let ctxt = iface_ref.signal_context();
+        let result = if added {
+            DBusInterface::xwayland_added(ctxt, path.to_string()).await
+        } else {
+            DBusInterface::xwayland_removed(ctxt, path.to_string()).await
+        };
+        if let Err(e) = result {
+            log::warn!("Unable to signal xwayland change for {path}: {:?}", e);
+        }
+        self.emit_properties_invalidated(&["Xwaylands"]).await;
+    }
+
+    /// Emits the [DBusInterface] PrimaryChanged signal and a change of the
+    /// PrimaryXWaylandPath property with the given path
+    async fn emit_primary_changed(&self, path: &str) {
+        let Ok(iface_ref) = self
+            .dbus
+            .object_server()
+            .interface::<_, DBusInterface>(self.manager_path())
+            .await
+        else {
+            log::debug!("Manager interface not served yet. Not signaling for {path}");
+            return;
+        };
+        let ctxt = iface_ref.signal_context();
+        if let Err(e) = DBusInterface::primary_changed(ctxt, path.to_string()).await {
+            log::warn!("Unable to signal primary change for {path}: {:?}", e);
+        }
+
+        // The property value is sent along directly, since the property getter
+        // requires a response from the manager loop this is called from.
+        let value = Value::from(path);
+        let changed = HashMap::from([("PrimaryXWaylandPath", &value)]);
+        let result = fdo::Properties::properties_changed(
+            ctxt,
+            InterfaceName::from_static_str_unchecked("org.shadowblip.Gamescope.Manager"),
+            &changed,
+            &[],
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Unable to signal primary path change for {path}: {:?}", e);
+        }
+        self.emit_properties_invalidated(&["Xwaylands"]).await;
+    }
+
+    /// Invalidates the given [DBusInterface] properties so clients re-read
+    /// them. The new values are not sent along because reading them requires
+    /// a response from the manager loop this is called from.
+    async fn emit_properties_invalidated(&self, properties: &[&str]) {
+        let Ok(iface_ref) = self
+            .dbus
+            .object_server()
+            .interface::<_, DBusInterface>(self.manager_path())
+            .await
+        else {
+            log::debug!("Manager interface not served yet. Not signaling {properties:?} change");
+            return;
+        };
+        let result = fdo::Properties::properties_changed(
+            iface_ref.signal_context(),
+            InterfaceName::from_static_str_unchecked("org.shadowblip.Gamescope.Manager"),
+            &HashMap::new(),
+            properties,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Unable to signal {properties:?} change: {:?}", e);
+        }
+    }
+
+    /// Returns the app usage stats tracked by the primary xwayland instance
+    async fn app_usage(&self) -> Vec<(u32, u32, u64)> {
+        let Some(path) = self.primary_path.clone() else {
+            return Vec::new();
+        };
+        match self
+            .dbus
+            .object_server()
+            .interface::<_, xwayland::DBusInterfacePrimary>(path)
+            .await
+        {
+            Ok(iface_ref) => iface_ref.get().await.app_usage(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Returns the DBus paths of all managed xwayland instances
+    fn xwayland_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.xwaylands.values().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    /// Returns the display name, DBus path and whether it is the primary
+    /// instance for each managed xwayland instance, sorted by display name.
+    /// Primacy is taken from the primary path cached by [sync_xwaylands].
+    fn xwayland_info(&self) -> Vec<(String, String, bool)> {
+        let mut xwaylands: Vec<(String, String, bool)> = self
+            .xwaylands
+            .iter()
+            .map(|(name, path)| {
+                let is_primary = self.primary_path.as_ref() == Some(path);
+                (name.clone(), path.clone(), is_primary)
+            })
+            .collect();
+        xwaylands.sort();
+        xwaylands
+    }
+
+    /// Returns the DBus paths of all managed wayland instances
+    fn wayland_paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self
+            .waylands
+            .iter()
+            .filter_map(|path| Self::wayland_dbus_path(&self.base_path, path).ok())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// Returns a handle to the managed wayland instance with the given socket
+    /// name (E.g. "gamescope-0"). If no instance matches, the only managed
+    /// instance is returned, or None if there is not exactly one.
+    async fn wayland_manager(
+        &self,
+        socket_name: Option<&str>,
+    ) -> Option<wayland::manager::WaylandManager> {
+        let matching = socket_name.and_then(|name| {
+            self.waylands
+                .iter()
+                .find(|path| Path::new(path).file_name().is_some_and(|file| file == name))
+        });
+        let socket_path = match matching {
+            Some(path) => path,
+            None if self.waylands.len() == 1 => self.waylands.iter().next()?,
+            None => return None,
+        };
+        let path = Self::wayland_dbus_path(&self.base_path, socket_path).ok()?;
+        let iface_ref = self
+            .dbus
+            .object_server()
+            .interface::<_, wayland::dbus::DBusInterface>(path)
+            .await
+            .ok()?;
+        let wayland = iface_ref.get().await.wayland();
+        Some(wayland)
+    }
+
+    /// Returns the socket path of each managed wayland instance along with
+    /// the DBus path it is served at
+    fn wayland_sockets(&self) -> Vec<(String, String)> {
+        let mut sockets: Vec<(String, String)> = self
+            .waylands
+            .iter()
+            .filter_map(|path| {
+                let dbus_path = Self::wayland_dbus_path(&self.base_path, path).ok()?;
+                Some((path.clone(), dbus_path))
+            })
+            .collect();
+        sockets.sort();
+        sockets
+    }
+
+    /// Force-removes any managed interface at the given DBus path from the
+    /// object server and stops tracking it. This is used to recover from
+    /// stale objects left behind by an inconsistent state.
+    async fn remove_object(&mut self, path: String) -> Result<(), Box<dyn Error>> {
+        let object_path = ObjectPath::try_from(path.clone())?;
+        let object_server = self.dbus.object_server();
+        let mut removed = false;
+        removed |= object_server
+            .remove::<xwayland::DBusInterface, ObjectPath>(object_path.clone())
+            .await
+            .is_ok();
+        removed |= object_server
+            .remove::<xwayland::DBusInterfacePrimary, ObjectPath>(object_path.clone())
+            .await
+            .is_ok();
+        removed |= object_server
+            .remove::<wayland::dbus::DBusInterface, ObjectPath>(object_path.clone())
+            .await
+            .is_ok();
+        removed |= object_server
+            .remove::<wayland::input_method::DBusInterface, ObjectPath>(object_path)
+            .await
+            .is_ok();
+
+        // Stop tracking the object so it can be re-discovered
+        let was_xwayland = self.xwaylands.values().any(|dbus_path| *dbus_path == path);
+        self.xwaylands.retain(|_, dbus_path| *dbus_path != path);
+        if self.primary_path.as_ref() == Some(&path) {
+            self.primary_path = None;
+            self.emit_primary_changed("").await;
+        }
+        let base_path = &self.base_path;
+        let wayland_count = self.waylands.len();
+        self.waylands.retain(|socket_path| {
+            Self::wayland_dbus_path(base_path, socket_path)
+                .map(|dbus_path| dbus_path != path)
+                .unwrap_or(true)
+        });
+        if self.waylands.len() != wayland_count {
+            self.emit_properties_invalidated(&["WaylandSockets"]).await;
+        }
+
+        if !removed {
+            return Err(format!("No managed object found at path: {path}").into());
+        }
+        log::info!("Removed object at path: {path}");
+        if was_xwayland {
+            self.emit_xwayland_signal(&path, false).await;
+        }
+
+        Ok(())
+    }
+
+    /// Removes all managed XWayland and Wayland objects from the bus and emits
+    /// the [DBusInterface] Shutdown signal. The Manager interface itself is
+    /// left for the caller to remove.
+    async fn shutdown(&mut self) {
+        log::info!("Shutting down manager");
+        let paths = [self.xwayland_paths(), self.wayland_paths()].concat();
+        for path in paths {
+            if let Err(e) = self.remove_object(path.clone()).await {
+                log::warn!("Failed to remove object at {path} during shutdown: {e}");
+            }
+        }
+
+        let Ok(iface_ref) = self
+            .dbus
+            .object_server()
+            .interface::<_, DBusInterface>(self.manager_path())
+            .await
+        else {
+            log::debug!("Manager interface not served. Not signaling shutdown");
+            return;
+        };
+        if let Err(e) = DBusInterface::shutdown(iface_ref.signal_context()).await {
+            log::warn!("Unable to signal shutdown: {:?}", e);
+        }
+    }
+
+    /// Sends a [Command::Heartbeat] to the manager at the given interval, so
+    /// the heartbeat signal is only emitted while the run loop is processing
+    /// commands. An interval of zero disables the heartbeat.
+    pub fn start_heartbeat(&self, interval: Duration) {
+        if interval.is_zero() {
+            log::debug!("Heartbeat disabled");
+            return;
+        }
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let mut timer = tokio::time::interval(interval);
+            // The first tick completes immediately
+            timer.tick().await;
+            loop {
+                timer.tick().await;
+                if tx.send(Command::Heartbeat).await.is_err() {
+                    log::debug!("Manager stopped. Stopping heartbeat");
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Emits the heartbeat signal with the current uptime and number of
+    /// managed instances
+    async fn emit_heartbeat(&self) {
+        let Ok(iface_ref) = self
+            .dbus
+            .object_server()
+            .interface::<_, DBusInterface>(self.manager_path())
+            .await
+        else {
+            log::debug!("Manager interface not served yet. Not signaling heartbeat");
+            return;
+        };
+        let result = DBusInterface::heartbeat(
+            iface_ref.signal_context(),
+            self.started.elapsed().as_secs(),
+            self.xwaylands.len() as u32,
+            self.waylands.len() as u32,
+        )
+        .await;
+        if let Err(e) = result {
+            log::warn!("Unable to signal heartbeat: {:?}", e);
+        }
     }
 
     /// Starts listening for [Command] messages to be sent from clients and
@@ -141,21 +723,94 @@ impl Manager {
                 Command::FilesystemEvent { event, watch_type } => {
                     self.on_watch_event(event, watch_type).await;
                 }
-                Command::XWaylandAdded { name: _ } | Command::XWaylandRemoved { name: _ } => {
-                    self.update_xwaylands().await?;
+                Command::XWaylandAdded { name } => {
+                    self.start_xwayland_discovery(Some(name));
+                }
+                Command::XWaylandRemoved { name: _ } => {
+                    self.start_xwayland_discovery(None);
+                }
+                Command::XWaylandsDiscovered {
+                    displays,
+                    generation,
+                } => {
+                    self.on_xwaylands_discovered(displays, generation).await?;
+                }
+                Command::WaylandEventsSettled { path, generation } => {
+                    self.on_wayland_events_settled(path, generation).await;
+                }
+                Command::WaylandStatus { sender } => {
+                    let status = self.wayland_status().await;
+                    if let Err(e) = sender.send(status).await {
+                        log::error!("Failed to send wayland status response: {:?}", e);
+                    }
+                }
+                Command::RemoveObject { path, sender } => {
+                    let result = self
+                        .remove_object(path)
+                        .await
+                        .map_err(|err| err.to_string());
+                    if let Err(e) = sender.send(result).await {
+                        log::error!("Failed to send remove object response: {:?}", e);
+                    }
+                }
+                Command::XWaylandPaths { sender } => {
+                    if let Err(e) = sender.send(self.xwayland_paths()).await {
+                        log::error!("Failed to send xwayland paths response: {:?}", e);
+                    }
+                }
+                Command::WaylandPaths { sender } => {
+                    if let Err(e) = sender.send(self.wayland_paths()).await {
+                        log::error!("Failed to send wayland paths response: {:?}", e);
+                    }
+                }
+                Command::XWaylands { sender } => {
+                    if let Err(e) = sender.send(self.xwayland_info()).await {
+                        log::error!("Failed to send xwaylands response: {:?}", e);
+                    }
                 }
-                Command::WaylandAdded { path } => {
-                    if !self.waylands.contains(&path) {
-                        // Added sleep here because sometimes we get errors regarding broken IO connection due to starting it too fast when gamescope is restarted
-                        tokio::time::sleep(Duration::from_secs(1)).await;
-                        self.start_wayland_manager_for_path(path).await;
+                Command::WaylandSockets { sender } => {
+                    if let Err(e) = sender.send(self.wayland_sockets()).await {
+                        log::error!("Failed to send wayland sockets response: {:?}", e);
                     }
                 }
-                Command::WaylandRemoved { path } => {
-                    if self.waylands.contains(&path) {
-                        self.remove_wayland_manager_for_path(path).await;
+                Command::AppUsage { sender } => {
+                    let usage = self.app_usage().await;
+                    if let Err(e) = sender.send(usage).await {
+                        log::error!("Failed to send app usage response: {:?}", e);
                     }
                 }
+                Command::PrimaryXWaylandPath { sender } => {
+                    let path = self.primary_path.clone().unwrap_or_default();
+                    if let Err(e) = sender.send(path).await {
+                        log::error!("Failed to send primary xwayland path response: {:?}", e);
+                    }
+                }
+                Command::PrimaryXWaylandName {
+                    socket_name,
+                    sender,
+                } => {
+                    let name = self.primary_xwayland_name(&socket_name).await;
+                    if let Err(e) = sender.send(name).await {
+                        log::error!("Failed to send primary xwayland name response: {:?}", e);
+                    }
+                }
+                Command::WaylandManager {
+                    socket_name,
+                    sender,
+                } => {
+                    let wayland = self.wayland_manager(socket_name.as_deref()).await;
+                    if let Err(e) = sender.send(wayland).await {
+                        log::error!("Failed to send wayland manager response: {:?}", e);
+                    }
+                }
+                Command::Shutdown { sender } => {
+                    self.shutdown().await;
+                    if let Err(e) = sender.send(()).await {
+                        log::error!("Failed to send shutdown response: {:?}", e);
+                    }
+                    break;
+                }
+                Command::Heartbeat => self.emit_heartbeat().await,
             }
         }
         log::warn!("Stopping manager");
@@ -190,6 +845,19 @@ impl Manager {
                     WatchType::X11 => self.on_x11_delete(name).await,
                 };
             }
+            WatchEvent::Overflow { path } => {
+                log::warn!("Events may have been missed on {path}. Rescanning.");
+                let result = match watch_type {
+                    WatchType::Wayland => self.update_waylands().await,
+                    WatchType::X11 => {
+                        self.start_xwayland_discovery(None);
+                        Ok(())
+                    }
+                };
+                if let Err(e) = result {
+                    log::error!("Failed to rescan {path}: {:?}", e);
+                }
+            }
             _ => (),
         }
     }
@@ -224,30 +892,52 @@ impl Manager {
         let _ = self.tx.send(Command::XWaylandRemoved { name }).await;
     }
 
-    async fn on_wayland_create(&self, name: String, path: String) {
+    async fn on_wayland_create(&mut self, name: String, path: String) {
         if !is_gamescope_socket_file(&name) {
             return;
         }
-
-        let _ = self
-            .tx
-            .send(Command::WaylandAdded {
-                path: format!("{path}/{name}"),
-            })
-            .await;
+        self.coalesce_wayland_event(format!("{path}/{name}"));
     }
 
-    async fn on_wayland_delete(&self, name: String, path: String) {
+    async fn on_wayland_delete(&mut self, name: String, path: String) {
         if !is_gamescope_socket_file(&name) {
             return;
         }
+        self.coalesce_wayland_event(format!("{path}/{name}"));
+    }
 
-        let _ = self
-            .tx
-            .send(Command::WaylandRemoved {
-                path: format!("{path}/{name}"),
-            })
-            .await;
+    /// Records a filesystem event for the given wayland socket and schedules
+    /// a [Command::WaylandEventsSettled] once no further events arrive. Only
+    /// the latest event for a path is acted on, so create/delete churn while
+    /// gamescope restarts results in a single add or remove.
+    fn coalesce_wayland_event(&mut self, path: String) {
+        let generation = self.wayland_events.record(&path);
+
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(WAYLAND_EVENT_SETTLE_TIME).await;
+            let _ = tx
+                .send(Command::WaylandEventsSettled { path, generation })
+                .await;
+        });
+    }
+
+    /// Adds or removes the wayland manager for the given socket based on
+    /// whether the socket exists once its filesystem events have settled.
+    /// Stale generations are ignored since a newer event is still pending.
+    async fn on_wayland_events_settled(&mut self, path: String, generation: u64) {
+        if !self.wayland_events.settle(&path, generation) {
+            log::debug!("Ignoring superseded events for {path}");
+            return;
+        }
+
+        let exists = Path::new(&path).exists();
+        let managed = self.waylands.contains(&path);
+        if exists && !managed {
+            self.start_wayland_manager_for_path(path).await;
+        } else if !exists && managed {
+            self.remove_wayland_manager_for_path(path).await;
+        }
     }
 
     /// Watches for new wayland instances to start and adds them.
@@ -300,108 +990,215 @@ impl Manager {
         Ok(())
     }
 
+    /// Discovers gamescope displays, retrying with increasing delays until the
+    /// expected display is found. The X display cannot be discovered right
+    /// after its socket is created, so discovery is always delayed. Without
+    /// an expected display, discovery is only attempted once.
+    async fn discover_xwaylands(expected: Option<&str>) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut attempt = 1;
+        loop {
+            tokio::time::sleep(XWAYLAND_DISCOVERY_DELAYS[attempt - 1]).await;
+            let result = gamescope_x11_client::discover_gamescope_displays();
+            let found = match (&result, expected) {
+                (Ok(displays), Some(expected)) => displays.iter().any(|name| name == expected),
+                (Ok(_), None) => true,
+                (Err(_), _) => false,
+            };
+            if found || attempt == XWAYLAND_DISCOVERY_DELAYS.len() {
+                if let Some(expected) = expected {
+                    log::info!(
+                        "Discovery of {expected} finished after {attempt} attempt(s), found: {found}"
+                    );
+                }
+                return result;
+            }
+            log::debug!("Display {expected:?} not discovered on attempt {attempt}. Retrying.");
+            attempt += 1;
+        }
+    }
+
+    /// Discovers gamescope displays in a separate task so the command loop is
+    /// not blocked while discovery waits, and posts the result back as
+    /// [Command::XWaylandsDiscovered]. If an expected display name is given
+    /// (E.g. ":1"), discovery is retried until it is found.
+    fn start_xwayland_discovery(&mut self, expected: Option<String>) {
+        self.xwayland_discovery += 1;
+        let generation = self.xwayland_discovery;
+
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let displays = match Self::discover_xwaylands(expected.as_deref()).await {
+                Ok(displays) => displays,
+                Err(e) => {
+                    log::error!("Failed to discover XWaylands: {:?}", e);
+                    return;
+                }
+            };
+            let _ = tx
+                .send(Command::XWaylandsDiscovered {
+                    displays,
+                    generation,
+                })
+                .await;
+        });
+    }
+
+    /// Adds/removes xwayland interfaces using the displays found by a
+    /// discovery task. Results that finish after those of a newer discovery
+    /// are stale and ignored.
+    async fn on_xwaylands_discovered(
+        &mut self,
+        displays: Vec<String>,
+        generation: u64,
+    ) -> Result<(), Box<dyn Error>> {
+        if generation < self.xwayland_discovery_applied {
+            log::debug!("Ignoring superseded XWayland discovery {generation}");
+            return Ok(());
+        }
+        self.xwayland_discovery_applied = generation;
+        self.sync_xwaylands(displays).await
+    }
+
     /// Discovers and adds/removes xwayland interfaces
     pub async fn update_xwaylands(&mut self) -> Result<(), Box<dyn Error>> {
-        log::info!("Updating XWaylands");
-
-        // Wait a small amount of time to allow the socket to be available
-        // NOTE: Without this, it seems that we cannot discover the X display
-        tokio::time::sleep(Duration::from_millis(500)).await;
+        let current_xwaylands = Self::discover_xwaylands(None).await?;
+        self.sync_xwaylands(current_xwaylands).await
+    }
 
-        // Discover new gamescopes
-        let current_xwaylands = gamescope_x11_client::discover_gamescope_displays()?;
+    /// Adds interfaces for the given xwayland displays that are not managed
+    /// yet and removes the interfaces of displays that no longer exist, in the
+    /// order given by [plan_xwayland_sync]
+    async fn sync_xwaylands(
+        &mut self,
+        current_xwaylands: Vec<String>,
+    ) -> Result<(), Box<dyn Error>> {
+        log::info!("Updating XWaylands");
         log::debug!("Discovered XWaylands: {:?}", current_xwaylands);
 
-        // Remove any xwaylands that no longer exist
-        let mut to_remove: Vec<String> = Vec::new();
-        for (name, dbus_path) in self.xwaylands.iter() {
-            if current_xwaylands.contains(name) {
-                log::debug!("XWayland still exists for {}. Skipping.", name);
-                continue;
-            }
-
-            log::info!("XWayland was removed: {}", name);
-            let path = ObjectPath::from_string_unchecked(dbus_path.clone());
-            self.dbus
-                .object_server()
-                .remove::<xwayland::DBusInterface, ObjectPath>(path.clone())
-                .await?;
-            let _ = self
-                .dbus
-                .object_server()
-                .remove::<xwayland::DBusInterfacePrimary, ObjectPath>(path)
-                .await;
-            to_remove.push(name.clone());
-        }
-        for name in to_remove {
-            self.xwaylands.remove(&name);
+        // Check which of the new displays are primary instances
+        let mut discovered = Vec::with_capacity(current_xwaylands.len());
+        for name in current_xwaylands {
+            let is_primary = if self.xwaylands.contains_key(&name) {
+                log::debug!("XWayland is already managed for {}. Skipping.", name);
+                false
+            } else {
+                xwayland::is_primary_display(&name)?
+            };
+            discovered.push((name, is_primary));
         }
 
-        // Create any xwaylands that don't exist
-        for (i, name) in current_xwaylands.into_iter().enumerate() {
-            if self.xwaylands.contains_key(&name) {
-                log::debug!("XWayland is already managed for {}. Skipping.", name);
-                continue;
+        let changes = plan_xwayland_sync(
+            &self.base_path,
+            &self.xwaylands,
+            self.primary_path.as_deref(),
+            &discovered,
+        );
+        for change in changes {
+            match change {
+                XWaylandChange::Add {
+                    name,
+                    path,
+                    primary,
+                } => self.add_xwayland(name, path, primary).await?,
+                XWaylandChange::Remove { name, path } => self.remove_xwayland(name, path).await?,
+                XWaylandChange::PrimaryChanged { path } => {
+                    log::info!("Primary XWayland changed to: {:?}", path);
+                    self.primary_path = path;
+                    let path = self.primary_path.clone().unwrap_or_default();
+                    self.emit_primary_changed(&path).await;
+                }
             }
+        }
 
-            // Create a new DBus interface to the xwayland instance
-            let path = format!("/org/shadowblip/Gamescope/XWayland{}", i);
-            let instance =
-                xwayland::DBusInterface::new(name.clone(), path.clone(), self.dbus.clone())?;
-            let is_primary = instance.primary().await?;
+        log::info!("Managed XWaylands: {:?}", self.xwaylands);
+
+        Ok(())
+    }
+
+    /// Adds the interfaces of the given xwayland display at the given path,
+    /// including the primary interface if it is a primary instance
+    async fn add_xwayland(
+        &mut self,
+        name: String,
+        path: String,
+        is_primary: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        // Create a new DBus interface to the xwayland instance
+        let instance = xwayland::DBusInterface::new(name.clone(), path.clone(), self.dbus.clone())?;
+
+        // Listen for new windows lifecycle
+        let window_lifecycle_rx = instance.listen_for_window_lifecycle()?;
+        // Propagate gamescope changes to DBus signals
+        xwayland::dispatch_window_lifecycle(
+            self.dbus.clone(),
+            path.clone(),
+            window_lifecycle_rx,
+            is_primary,
+        )
+        .await?;
+
+        // If this is a primary xwayland instance, also attach the dbus
+        // interface with extra methods
+        if is_primary {
+            log::debug!("Discovered XWayland {} is primary", name);
+
+            // Property changes events
+            let primary = xwayland::DBusInterfacePrimary::new(
+                name.clone(),
+                path.clone(),
+                self.dbus.clone(),
+                self.tx.clone(),
+            )?;
+            let property_changes_rx = primary.listen_for_property_changes()?;
+            let focusable_apps_changes = primary.focusable_apps_changes();
+            #[allow(deprecated)]
+            let window_created_rx = primary.listen_for_window_created()?;
+            self.dbus.object_server().at(path.clone(), primary).await?;
 
-            // Listen for new windows lifecycle
-            let window_lifecycle_rx = instance.listen_for_window_lifecycle()?;
             // Propagate gamescope changes to DBus signals
-            xwayland::dispatch_window_lifecycle(
+            xwayland::dispatch_primary_property_changes(
                 self.dbus.clone(),
                 path.clone(),
-                window_lifecycle_rx,
-                is_primary,
+                property_changes_rx,
+                focusable_apps_changes,
             )
             .await?;
 
-            // Check to see if this is a primary xwayland instance. If it is,
-            // also attach the dbus interface with extra methods
-            if is_primary {
-                log::debug!("Discovered XWayland {} is primary", name);
-
-                // Property changes events
-                let primary = xwayland::DBusInterfacePrimary::new(
-                    name.clone(),
-                    path.clone(),
-                    self.dbus.clone(),
-                )?;
-                let property_changes_rx = primary.listen_for_property_changes()?;
-                #[allow(deprecated)]
-                let window_created_rx = primary.listen_for_window_created()?;
-                self.dbus.object_server().at(path.clone(), primary).await?;
-
-                // Propagate gamescope changes to DBus signals
-                xwayland::dispatch_primary_property_changes(
-                    self.dbus.clone(),
-                    path.clone(),
-                    property_changes_rx,
-                )
-                .await?;
-
-                // Propagate gamescope changes to DBus signals
-                #[allow(deprecated)]
-                xwayland::dispatch_primary_window_created(
-                    self.dbus.clone(),
-                    path.clone(),
-                    window_created_rx,
-                )
-                .await?;
-            }
+            // Propagate gamescope changes to DBus signals
+            #[allow(deprecated)]
+            xwayland::dispatch_primary_window_created(
+                self.dbus.clone(),
+                path.clone(),
+                window_created_rx,
+            )
+            .await?;
+        }
 
-            self.dbus.object_server().at(path.clone(), instance).await?;
+        self.dbus.object_server().at(path.clone(), instance).await?;
 
-            // Add the instance
-            self.xwaylands.insert(name, path);
-        }
+        // Add the instance
+        self.xwaylands.insert(name, path.clone());
+        self.emit_xwayland_signal(&path, true).await;
 
-        log::info!("Managed XWaylands: {:?}", self.xwaylands);
+        Ok(())
+    }
+
+    /// Removes the interfaces of the given xwayland display that no longer
+    /// exists
+    async fn remove_xwayland(&mut self, name: String, path: String) -> Result<(), Box<dyn Error>> {
+        log::info!("XWayland was removed: {}", name);
+        let object_path = ObjectPath::from_string_unchecked(path.clone());
+        self.dbus
+            .object_server()
+            .remove::<xwayland::DBusInterface, ObjectPath>(object_path.clone())
+            .await?;
+        let _ = self
+            .dbus
+            .object_server()
+            .remove::<xwayland::DBusInterfacePrimary, ObjectPath>(object_path)
+            .await;
+        self.xwaylands.remove(&name);
+        self.emit_xwayland_signal(&path, false).await;
 
         Ok(())
     }
@@ -449,15 +1246,17 @@ impl Manager {
 
 /// DBus interface imeplementation for Gamescope Manager instance
 pub struct DBusInterface {
-    //// Connection to the DBus bus
-    //dbus: Connection,
+    /// Transmit side of the [Manager] command channel
+    tx: mpsc::Sender<Command>,
+    /// Base DBus path that all objects are served under
+    base_path: String,
 }
 
 impl DBusInterface {
-    /// Returns a new instance of the XWayland DBus interface. Will error if
-    /// it cannot establish a connection.
-    pub fn new() -> DBusInterface {
-        DBusInterface {}
+    /// Returns a new instance of the Manager DBus interface that communicates
+    /// with the [Manager] over the given channel.
+    pub fn new(tx: mpsc::Sender<Command>, base_path: String) -> DBusInterface {
+        DBusInterface { tx, base_path }
     }
 }
 
@@ -467,4 +1266,350 @@ impl DBusInterface {
     async fn name(&self) -> fdo::Result<String> {
         Ok("Manager".into())
     }
+
+    /// DBus paths of all managed XWayland instances. Paths may change as
+    /// Gamescope instances are started and stopped, so clients should re-read
+    /// this property instead of caching it.
+    #[dbus_interface(property)]
+    async fn xwayland_paths(&self) -> fdo::Result<Vec<String>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::XWaylandPaths { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// Display name, DBus path and whether it is the primary instance for each
+    /// managed XWayland instance (E.g. ":1", "/org/shadowblip/Gamescope/XWayland1", true)
+    #[dbus_interface(property)]
+    async fn xwaylands(&self) -> fdo::Result<Vec<(String, String, bool)>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::XWaylands { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// DBus path of the primary XWayland instance, or an empty string if
+    /// there is no primary instance
+    #[dbus_interface(property)]
+    async fn primary_xwayland_path(&self) -> fdo::Result<String> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::PrimaryXWaylandPath { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// DBus paths of all managed Wayland instances. Paths may change as
+    /// Gamescope instances are started and stopped, so clients should re-read
+    /// this property instead of caching it.
+    #[dbus_interface(property)]
+    async fn wayland_paths(&self) -> fdo::Result<Vec<String>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::WaylandPaths { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// Socket path of each managed Wayland instance along with the DBus path
+    /// it is served at (E.g. "/run/user/1000/gamescope-0",
+    /// "/org/shadowblip/Gamescope/Wayland0")
+    #[dbus_interface(property)]
+    async fn wayland_sockets(&self) -> fdo::Result<Vec<(String, String)>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::WaylandSockets { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// Emitted after a new XWayland instance is served on the bus
+    #[dbus_interface(signal, name = "XWaylandAdded")]
+    async fn xwayland_added(ctxt: &SignalContext<'_>, path: String) -> zbus::Result<()>;
+
+    /// Emitted after an XWayland instance is removed from the bus
+    #[dbus_interface(signal, name = "XWaylandRemoved")]
+    async fn xwayland_removed(ctxt: &SignalContext<'_>, path: String) -> zbus::Result<()>;
+
+    /// Emitted when the primary XWayland instance changes, such as when
+    /// gamescope restarts. The new primary interface is fully served on the
+    /// bus before this fires. The path is empty if there is no primary.
+    #[dbus_interface(signal)]
+    async fn primary_changed(ctxt: &SignalContext<'_>, path: String) -> zbus::Result<()>;
+
+    /// Emitted when the daemon is shutting down, after all XWayland and
+    /// Wayland objects have been removed from the bus
+    #[dbus_interface(signal)]
+    async fn shutdown(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
+
+    /// Emitted periodically while the manager is processing commands, with
+    /// the daemon uptime and the number of managed XWayland and Wayland
+    /// instances
+    #[dbus_interface(signal)]
+    async fn heartbeat(
+        ctxt: &SignalContext<'_>,
+        uptime_secs: u64,
+        xwaylands: u32,
+        waylands: u32,
+    ) -> zbus::Result<()>;
+
+    /// Returns the socket path of each managed wayland instance and whether
+    /// the gamescope_control global was bound on it. A socket that is managed
+    /// but has no control bound is likely stale or not a gamescope socket.
+    async fn wayland_status(&self) -> fdo::Result<Vec<(String, bool)>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::WaylandStatus { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// Returns the (app_id, focus_count, last_focused_ms) of every app that has
+    /// been focused on the primary XWayland instance, most recently focused
+    /// first. Stats are kept in memory and reset when the daemon or gamescope
+    /// restarts.
+    async fn get_app_usage(&self) -> fdo::Result<Vec<(u32, u32, u64)>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::AppUsage { sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))
+    }
+
+    /// Runs gamescope display discovery and returns the names of all X
+    /// displays found (E.g. ":0", ":1"), whether or not they are currently
+    /// served on the bus.
+    async fn discover_displays(&self) -> fdo::Result<Vec<String>> {
+        let mut displays = gamescope_x11_client::discover_gamescope_displays().map_err(|err| {
+            log::error!("Failed to discover gamescope displays: {err}");
+            fdo::Error::Failed(format!("Failed to discover gamescope displays: {err}"))
+        })?;
+        displays.sort();
+        Ok(displays)
+    }
+
+    /// Maximum level of messages logged by the daemon. One of "trace",
+    /// "debug", "info", "warn" or "error".
+    #[dbus_interface(property)]
+    async fn log_level(&self) -> String {
+        logger::level()
+    }
+
+    /// Changes the maximum level of messages logged by the daemon without
+    /// restarting it. Accepts "trace", "debug", "info", "warn" or "error".
+    async fn set_log_level(
+        &self,
+        level: String,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        logger::set_level(&level).map_err(fdo::Error::InvalidArgs)?;
+        log::info!("Log level set to {}", logger::level());
+        self.log_level_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Returns up to `max` of the most recent lines logged by the daemon,
+    /// oldest first. At most 1000 lines are kept in memory.
+    async fn get_recent_logs(&self, max: u32) -> Vec<String> {
+        logger::recent_logs(max as usize)
+    }
+
+    /// Force-removes a stale XWayland or Wayland object at the given path.
+    /// Only paths beneath the base path (E.g. "/org/shadowblip/Gamescope/")
+    /// are allowed, and the Manager object itself cannot be removed. Removed
+    /// instances that still exist will be re-added on the next discovery.
+    async fn remove_object(&self, path: String) -> fdo::Result<()> {
+        let prefix = format!("{}/", self.base_path);
+        if !path.starts_with(&prefix) || path.ends_with("/Manager") {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Refusing to remove object at path: {path}"
+            )));
+        }
+
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.tx
+            .send(Command::RemoveObject { path, sender })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        receiver
+            .recv()
+            .await
+            .ok_or_else(|| fdo::Error::Failed("No response received from manager".into()))?
+            .map_err(fdo::Error::Failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{plan_xwayland_sync, EventGenerations, XWaylandChange};
+
+    const SOCKET: &str = "/run/user/1000/gamescope-0";
+
+    #[test]
+    fn rapid_events_settle_once() {
+        let mut events = EventGenerations::default();
+
+        // Create, delete, create while gamescope restarts
+        let generations = [
+            events.record(SOCKET),
+            events.record(SOCKET),
+            events.record(SOCKET),
+        ];
+
+        // Only the settle check of the last event acts
+        let settled: Vec<bool> = generations
+            .iter()
+            .map(|generation| events.settle(SOCKET, *generation))
+            .collect();
+        assert_eq!(settled, [false, false, true]);
+    }
+
+    #[test]
+    fn settled_paths_are_forgotten() {
+        let mut events = EventGenerations::default();
+        let generation = events.record(SOCKET);
+        assert!(events.settle(SOCKET, generation));
+        assert!(!events.settle(SOCKET, generation));
+
+        // A later event settles on its own
+        let generation = events.record(SOCKET);
+        assert!(events.settle(SOCKET, generation));
+    }
+
+    #[test]
+    fn paths_settle_independently() {
+        let mut events = EventGenerations::default();
+        let other = "/run/user/1000/gamescope-1";
+        let first = events.record(SOCKET);
+        let second = events.record(other);
+        events.record(SOCKET);
+
+        assert!(!events.settle(SOCKET, first));
+        assert!(events.settle(other, second));
+    }
+
+    const BASE_PATH: &str = "/org/shadowblip/Gamescope";
+
+    fn managed(displays: &[(&str, &str)]) -> HashMap<String, String> {
+        displays
+            .iter()
+            .map(|(name, path)| (name.to_string(), format!("{BASE_PATH}/{path}")))
+            .collect()
+    }
+
+    fn discovered(displays: &[(&str, bool)]) -> Vec<(String, bool)> {
+        displays
+            .iter()
+            .map(|(name, primary)| (name.to_string(), *primary))
+            .collect()
+    }
+
+    #[test]
+    fn new_xwaylands_are_added_before_old_ones_are_removed() {
+        // Gamescope restarted and now serves new displays
+        let managed = managed(&[(":0", "XWayland0"), (":1", "XWayland1")]);
+        let primary_path = format!("{BASE_PATH}/XWayland0");
+        let discovered = discovered(&[(":2", true), (":3", false)]);
+
+        let changes = plan_xwayland_sync(BASE_PATH, &managed, Some(&primary_path), &discovered);
+
+        let path = |name: &str| format!("{BASE_PATH}/{name}");
+        assert_eq!(
+            changes,
+            [
+                XWaylandChange::Add {
+                    name: ":2".into(),
+                    path: path("XWayland2"),
+                    primary: true,
+                },
+                XWaylandChange::Add {
+                    name: ":3".into(),
+                    path: path("XWayland3"),
+                    primary: false,
+                },
+                XWaylandChange::Remove {
+                    name: ":0".into(),
+                    path: path("XWayland0"),
+                },
+                XWaylandChange::Remove {
+                    name: ":1".into(),
+                    path: path("XWayland1"),
+                },
+                XWaylandChange::PrimaryChanged {
+                    path: Some(path("XWayland2")),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_changed_comes_last() {
+        let managed = managed(&[(":0", "XWayland0")]);
+        let primary_path = format!("{BASE_PATH}/XWayland0");
+        let discovered = discovered(&[(":0", false), (":1", true)]);
+
+        let changes = plan_xwayland_sync(BASE_PATH, &managed, Some(&primary_path), &discovered);
+
+        let position = changes
+            .iter()
+            .position(|change| matches!(change, XWaylandChange::PrimaryChanged { .. }));
+        assert_eq!(position, Some(changes.len() - 1));
+    }
+
+    #[test]
+    fn removed_primary_without_replacement_is_cleared() {
+        let managed = managed(&[(":0", "XWayland0"), (":1", "XWayland1")]);
+        let primary_path = format!("{BASE_PATH}/XWayland0");
+        let discovered = discovered(&[(":1", false)]);
+
+        let changes = plan_xwayland_sync(BASE_PATH, &managed, Some(&primary_path), &discovered);
+
+        assert_eq!(
+            changes.last(),
+            Some(&XWaylandChange::PrimaryChanged { path: None })
+        );
+    }
+
+    #[test]
+    fn unchanged_xwaylands_need_no_changes() {
+        let managed = managed(&[(":0", "XWayland0"), (":1", "XWayland1")]);
+        let primary_path = format!("{BASE_PATH}/XWayland0");
+        let discovered = discovered(&[(":0", false), (":1", false)]);
+
+        let changes = plan_xwayland_sync(BASE_PATH, &managed, Some(&primary_path), &discovered);
+
+        assert!(changes.is_empty());
+    }
 }
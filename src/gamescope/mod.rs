@@ -1,3 +1,4 @@
 pub mod manager;
 pub mod wayland;
+pub mod x11;
 pub mod xwayland;
@@ -1,9 +1,69 @@
-use std::error::Error;
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt::Write,
+    fs::{self, DirBuilder, OpenOptions},
+    hash::{BuildHasher, Hasher},
+    io::ErrorKind,
+    os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use gamescope_wayland_client::control::gamescope_control::ScreenshotType;
-use zbus::{dbus_interface, fdo, Connection};
+use gamescope_x11_client::xwayland::{Primary, XWayland};
+use nix::unistd::{access, AccessFlags, Uid};
+use tokio::sync::mpsc;
+use zbus::{dbus_interface, fdo, zvariant::Type, Connection};
 
-use super::manager::{screenshot_type_from_u8, WaylandManager, WaylandMessage};
+use crate::{
+    gamescope::{manager::Command, x11::X11Client, xwayland::HDR_ENABLED_ATOM},
+    utils::get_run_user_dir,
+};
+
+use super::manager::{
+    feature_name, screenshot_type_from_u8, ScreenshotError, WaylandManager, WaylandMessage,
+    SCREENSHOT_TIMEOUT,
+};
+
+/// Maximum size of a DBus array in bytes
+const MAX_DBUS_ARRAY_SIZE: usize = 64 * 1024 * 1024;
+
+/// Screenshot file path along with metadata gathered at capture time. Fields
+/// that could not be determined are zeroed.
+#[derive(Type, serde::Serialize)]
+pub struct ScreenshotResult {
+    pub file_path: String,
+    /// Unix time in milliseconds when the screenshot was requested
+    pub timestamp_ms: u64,
+    pub screenshot_type: u8,
+    /// Focused app id on the primary XWayland instance
+    pub app_id: u32,
+    /// Focused window id on the primary XWayland instance
+    pub window_id: u32,
+    /// Name of the focused window
+    pub window_name: String,
+    /// Size of the primary XWayland root window
+    pub width: u16,
+    pub height: u16,
+    /// Whether HDR output was enabled
+    pub hdr_enabled: bool,
+}
+
+/// Metadata read from the primary XWayland instance of a gamescope instance
+#[derive(Debug, Default)]
+struct PrimaryMetadata {
+    app_id: u32,
+    window_id: u32,
+    window_name: String,
+    width: u16,
+    height: u16,
+    hdr_enabled: bool,
+}
 
 /// DBus interface implementation for Gamescope Wayland instance.
 #[allow(dead_code)]
@@ -11,6 +71,15 @@ pub struct DBusInterface {
     path: String,
     wayland: WaylandManager,
     dbus: Connection,
+    /// Channel to the gamescope manager
+    manager_tx: mpsc::Sender<Command>,
+    /// File name of the wayland socket (E.g. "gamescope-0")
+    socket_name: String,
+    /// Minimum amount of time in milliseconds between screenshots. Zero
+    /// disables the guard.
+    screenshot_min_interval_ms: AtomicU64,
+    /// Time the last screenshot was requested
+    last_screenshot: Mutex<Option<Instant>>,
 }
 
 #[allow(dead_code)]
@@ -20,15 +89,89 @@ impl DBusInterface {
     pub async fn new(
         path: String,
         dbus: Connection,
+        manager_tx: mpsc::Sender<Command>,
         socket_path: String,
     ) -> Result<DBusInterface, Box<dyn Error>> {
+        let socket_name = Path::new(&socket_path)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
         let wayland = WaylandManager::new(socket_path).await?;
 
         Ok(DBusInterface {
             path,
             wayland,
             dbus,
+            manager_tx,
+            socket_name,
+            screenshot_min_interval_ms: AtomicU64::new(0),
+            last_screenshot: Mutex::new(None),
+        })
+    }
+
+    /// Returns a handle to the wayland manager used by this interface
+    pub fn wayland(&self) -> WaylandManager {
+        self.wayland.clone()
+    }
+
+    /// Returns whether the gamescope_control global was bound on the socket
+    pub fn control_bound(&self) -> bool {
+        self.wayland.control_bound()
+    }
+
+    /// Returns the (feature, version, flags) of each feature reported as
+    /// supported by gamescope, sorted by feature id.
+    async fn get_features(&self) -> fdo::Result<Vec<(u32, u32, u32)>> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<(u32, u32, u32)>>(1);
+        self.wayland
+            .send(WaylandMessage::CommandGetFeatures(tx))
+            .await
+            .map_err(|err| to_fdo_error("Error when sending get features command", err))?;
+
+        rx.recv()
+            .await
+            .ok_or_else(|| fdo_error("No response received for get features command"))
+    }
+
+    /// Returns the display name (E.g. ":1") of the primary XWayland instance
+    /// of the gamescope serving this wayland socket
+    async fn primary_display(&self) -> Result<String, Box<dyn Error>> {
+        let (sender, mut receiver) = mpsc::channel(1);
+        self.manager_tx
+            .send(Command::PrimaryXWaylandName {
+                socket_name: self.socket_name.clone(),
+                sender,
+            })
+            .await?;
+        receiver
+            .recv()
+            .await
+            .flatten()
+            .ok_or_else(|| "No primary XWayland instance found for this socket".into())
+    }
+
+    /// Reads [PrimaryMetadata] from the primary XWayland instance of the
+    /// gamescope serving this wayland socket. The X11 requests are made on
+    /// the blocking thread pool.
+    async fn primary_metadata(&self) -> Result<PrimaryMetadata, Box<dyn Error>> {
+        let name = self.primary_display().await?;
+        let metadata = tokio::task::spawn_blocking(move || {
+            read_primary_metadata(&name).map_err(|err| err.to_string())
         })
+        .await??;
+        Ok(metadata)
+    }
+
+    /// Returns the focused app id on the primary XWayland instance of the
+    /// gamescope serving this wayland socket. The X11 requests are made on the
+    /// blocking thread pool.
+    async fn focused_app(&self) -> Result<u32, Box<dyn Error>> {
+        let name = self.primary_display().await?;
+        let app_id = tokio::task::spawn_blocking(move || {
+            read_focused_app(&name).map_err(|err| err.to_string())
+        })
+        .await??;
+        Ok(app_id)
     }
 
     /// Returns a reference to the dbus interface
@@ -44,18 +187,54 @@ impl DBusInterface {
 #[dbus_interface(name = "org.shadowblip.Gamescope.Wayland")]
 impl DBusInterface {
     /// Takes a screenshot using Wayland
+    /// the file_path may contain strftime-style format specifiers
+    /// (E.g. "/tmp/shot-%Y%m%d-%H%M%S.png") which are resolved using the
+    /// local time
     /// the screenshot_type u8 converts to [ScreenshotType]
     /// 0 => [ScreenshotType::AllRealLayers]
     /// 1 => [ScreenshotType::BasePlaneOnly]
     /// 2 => [ScreenshotType::FullComposition]
     /// 3 => [ScreenshotType::ScreenBuffer]
     pub async fn take_screenshot(&self, file_path: String, screenshot_type: u8) -> fdo::Result<()> {
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<(), String>>(16);
+        self.take_screenshot_with_path(file_path, screenshot_type)
+            .await?;
+        Ok(())
+    }
+
+    /// Takes a screenshot like [take_screenshot] and returns the path that
+    /// gamescope saved the screenshot to, which may differ from the requested
+    /// path. Format specifiers in the path are resolved before the request is
+    /// sent. Returns a TimedOut error if gamescope does not report the
+    /// screenshot in time, a NotSupported error if the running gamescope does
+    /// not support the screenshot type, or a FileNotFound error if the
+    /// reported file does not exist.
+    pub async fn take_screenshot_with_path(
+        &self,
+        file_path: String,
+        screenshot_type: u8,
+    ) -> fdo::Result<String> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<String, ScreenshotError>>(16);
         let Some(screenshot_type): Option<ScreenshotType> =
             screenshot_type_from_u8(screenshot_type)
         else {
             return Err(fdo_error("Invalid screenshot type"));
         };
+        let file_path = resolve_screenshot_path(&file_path)?;
+
+        {
+            let min_interval =
+                Duration::from_millis(self.screenshot_min_interval_ms.load(Ordering::SeqCst));
+            let mut last_screenshot = self
+                .last_screenshot
+                .lock()
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if let Some(last_screenshot) = *last_screenshot {
+                if last_screenshot.elapsed() < min_interval {
+                    return Err(fdo_error("screenshot rate limited"));
+                }
+            }
+            *last_screenshot = Some(Instant::now());
+        }
 
         self.wayland
             .send(WaylandMessage::CommandTakeScreenshot(
@@ -66,15 +245,290 @@ impl DBusInterface {
             .await
             .map_err(|err| to_fdo_error("Error when sending screenshot command", err))?;
 
-        match rx.recv().await {
-            Some(Ok(_)) => {
-                log::info!("Screenshot taken");
-                Ok(())
+        match tokio::time::timeout(SCREENSHOT_TIMEOUT, rx.recv()).await {
+            Ok(Some(Ok(path))) => {
+                if !Path::new(&path).exists() {
+                    log::error!("[{}] Screenshot was not written to: {path}", self.path);
+                    return Err(fdo::Error::FileNotFound(format!(
+                        "Screenshot was not written to: {path}"
+                    )));
+                }
+                log::info!("[{}] Screenshot taken at path: {path}", self.path);
+                Ok(path)
+            }
+            Ok(Some(Err(ScreenshotError::Unsupported(err)))) => {
+                log::warn!("[{}] {err}", self.path);
+                Err(fdo::Error::NotSupported(err))
+            }
+            Ok(Some(Err(ScreenshotError::Failed(err)))) => {
+                Err(to_fdo_error("Error from screenshot command", err.into()))
+            }
+            Ok(Some(Err(ScreenshotError::TimedOut))) | Err(_) => {
+                log::error!("[{}] Timed out waiting for screenshot", self.path);
+                Err(fdo::Error::TimedOut(format!(
+                    "Timed out waiting for screenshot, gamescope may not support screenshot type {screenshot_type:?}"
+                )))
             }
-            Some(Err(err)) => Err(to_fdo_error("Error from screenshot command", err.into())),
-            None => Err(fdo_error("No response received for screenshot command")),
+            Ok(None) => Err(fdo_error("No response received for screenshot command")),
         }
     }
+
+    /// Takes a screenshot of only the given app and returns the path that
+    /// gamescope saved it to. Gamescope cannot capture an arbitrary app's
+    /// plane, so this uses [ScreenshotType::BasePlaneOnly], which contains
+    /// just the focused app without overlays. Returns a NotSupported error if
+    /// the app is not the focused app on the primary XWayland instance of the
+    /// gamescope serving this wayland socket.
+    pub async fn take_app_screenshot(&self, app_id: u32, file_path: String) -> fdo::Result<String> {
+        let focused_app_id = self
+            .focused_app()
+            .await
+            .map_err(|err| to_fdo_error("Unable to read the focused app", err))?;
+        if focused_app_id != app_id {
+            return Err(fdo::Error::NotSupported(format!(
+                "App {app_id} is not focused, gamescope can only capture the focused app"
+            )));
+        }
+
+        self.take_screenshot_with_path(file_path, 1).await
+    }
+
+    /// Takes a screenshot using Wayland and returns the file path along with
+    /// the focused app, focused window and its name, and resolution of the
+    /// primary XWayland instance of this gamescope at the time of capture. Metadata is gathered on a best-effort
+    /// basis and is zeroed if it cannot be read.
+    pub async fn take_screenshot_with_metadata(
+        &self,
+        screenshot_type: u8,
+    ) -> fdo::Result<ScreenshotResult> {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let file_path = create_screenshot_file()
+            .map_err(|err| to_fdo_error("Unable to create screenshot file", err))?;
+        let metadata = self.primary_metadata().await.unwrap_or_else(|err| {
+            log::warn!(
+                "[{}] Unable to read screenshot metadata: {err:?}",
+                self.path
+            );
+            PrimaryMetadata::default()
+        });
+
+        let file_path = self
+            .take_screenshot_with_path(file_path, screenshot_type)
+            .await?;
+
+        Ok(ScreenshotResult {
+            file_path,
+            timestamp_ms,
+            screenshot_type,
+            app_id: metadata.app_id,
+            window_id: metadata.window_id,
+            window_name: metadata.window_name,
+            width: metadata.width,
+            height: metadata.height,
+            hdr_enabled: metadata.hdr_enabled,
+        })
+    }
+
+    /// Takes a screenshot using Wayland and returns the PNG data instead of a
+    /// file path. The screenshot is written to a temporary file in a private
+    /// directory under the runtime directory, which is removed once it has
+    /// been read, even if reading fails. DBus limits arrays to 64 MiB, so
+    /// larger screenshots return an error.
+    pub async fn take_screenshot_to_bytes(&self, screenshot_type: u8) -> fdo::Result<Vec<u8>> {
+        let file_path = create_screenshot_file()
+            .map_err(|err| to_fdo_error("Unable to create screenshot file", err))?;
+
+        let result = self
+            .take_screenshot_with_path(file_path.clone(), screenshot_type)
+            .await;
+        let saved_path = result.as_ref().unwrap_or(&file_path).clone();
+        let bytes = match result {
+            Ok(_) => tokio::fs::read(&saved_path)
+                .await
+                .map_err(|err| to_fdo_error("Error reading screenshot", err.into())),
+            Err(err) => Err(err),
+        };
+
+        // Clean up the temporary file whether or not the screenshot succeeded
+        for path in [&file_path, &saved_path] {
+            if let Err(err) = tokio::fs::remove_file(path).await {
+                if err.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("[{}] Unable to remove screenshot {path}: {err}", self.path);
+                }
+            }
+        }
+
+        let bytes = bytes?;
+        if bytes.len() > MAX_DBUS_ARRAY_SIZE {
+            return Err(fdo_error("Screenshot is too large to send over DBus"));
+        }
+        Ok(bytes)
+    }
+
+    /// Whether the connection to the gamescope wayland display is alive. If
+    /// the connection is lost, reconnecting is attempted before the next
+    /// request is processed.
+    #[dbus_interface(property)]
+    async fn connected(&self) -> bool {
+        self.wayland.connected()
+    }
+
+    /// The (feature, version, flags) of each gamescope_control feature
+    /// supported by gamescope, sorted by feature id. Features announced after
+    /// the connection was made are included.
+    #[dbus_interface(property)]
+    pub async fn supported_features(&self) -> fdo::Result<Vec<(u32, u32, u32)>> {
+        self.get_features().await
+    }
+
+    /// Returns whether or not gamescope supports the gamescope_control feature
+    /// with the given id
+    pub async fn is_feature_supported(&self, feature: u32) -> fdo::Result<bool> {
+        let features = self.get_features().await?;
+        Ok(features.iter().any(|(id, _, _)| *id == feature))
+    }
+
+    /// Returns the ids of the gamescope_control features supported by gamescope
+    pub async fn get_supported_features(&self) -> fdo::Result<Vec<u32>> {
+        let features = self.get_features().await?;
+        Ok(features
+            .into_iter()
+            .map(|(feature, _, _)| feature)
+            .collect())
+    }
+
+    /// Returns the names of the gamescope_control features supported by
+    /// gamescope. Unknown feature ids are returned as "feature_<id>".
+    pub async fn get_supported_feature_names(&self) -> fdo::Result<Vec<String>> {
+        let features = self.get_features().await?;
+        Ok(features
+            .into_iter()
+            .map(|(feature, _, _)| feature_name(feature))
+            .collect())
+    }
+
+    /// Sets the minimum interval in milliseconds between screenshots. Calls
+    /// to [take_screenshot] made within this interval of the previous one will
+    /// fail. A value of 0 disables the guard.
+    pub async fn set_screenshot_min_interval_ms(&self, ms: u32) -> fdo::Result<()> {
+        self.screenshot_min_interval_ms
+            .store(ms as u64, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Returns the focused app id on the given primary XWayland display, or 0 if
+/// no app is focused. This makes blocking X11 requests.
+fn read_focused_app(name: &str) -> Result<u32, Box<dyn Error>> {
+    let mut xwayland = XWayland::new(name.to_string());
+    xwayland.connect()?;
+    Ok(xwayland.get_focused_app()?.unwrap_or_default())
+}
+
+/// Reads [PrimaryMetadata] from the given primary XWayland display. This
+/// makes blocking X11 requests.
+fn read_primary_metadata(name: &str) -> Result<PrimaryMetadata, Box<dyn Error>> {
+    let mut xwayland = XWayland::new(name.to_string());
+    xwayland.connect()?;
+    let app_id = xwayland.get_focused_app()?.unwrap_or_default();
+    let window_id = xwayland.get_focused_window()?.unwrap_or_default();
+    let window_name = match window_id {
+        0 => String::new(),
+        _ => xwayland.get_window_name(window_id)?.unwrap_or_default(),
+    };
+    let root_window_id = xwayland.get_root_window_id()?;
+    let geometry = xwayland.get_geometry_for_window(root_window_id)?;
+    let x11 = X11Client::connect(name)?;
+    let root_window_id = x11.get_root_window_id();
+    let hdr_enabled = x11
+        .get_cardinal_property(root_window_id, HDR_ENABLED_ATOM)?
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default()
+        != 0;
+
+    Ok(PrimaryMetadata {
+        app_id,
+        window_id,
+        window_name,
+        width: geometry.width,
+        height: geometry.height,
+        hdr_enabled,
+    })
+}
+
+/// Resolves strftime-style format specifiers in the given screenshot path
+/// using the local time, and checks that the resulting directory exists and
+/// is writable. Paths without format specifiers are returned unchanged.
+fn resolve_screenshot_path(template: &str) -> fdo::Result<String> {
+    if !template.contains('%') {
+        return Ok(template.to_owned());
+    }
+
+    let mut path = String::new();
+    write!(path, "{}", chrono::Local::now().format(template)).map_err(|_| {
+        fdo::Error::InvalidArgs(format!("Invalid format specifier in path: {template}"))
+    })?;
+
+    let dir = Path::new(&path)
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty());
+    if let Some(dir) = dir {
+        if !dir.is_dir() {
+            return Err(fdo::Error::FileNotFound(format!(
+                "Screenshot directory does not exist: {}",
+                dir.display()
+            )));
+        }
+        if access(dir, AccessFlags::W_OK).is_err() {
+            return Err(fdo::Error::AccessDenied(format!(
+                "Screenshot directory is not writable: {}",
+                dir.display()
+            )));
+        }
+    }
+
+    Ok(path)
+}
+
+/// Creates an empty file with a unique name for gamescope to save a
+/// screenshot to and returns its path. The file is created in a directory
+/// under the runtime directory that only the current user can access.
+fn create_screenshot_file() -> Result<String, Box<dyn Error>> {
+    let dir = Path::new(&get_run_user_dir()).join("gamescope-dbus");
+    match DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    // Refuse to use a directory that someone else could write to
+    let metadata = fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir()
+        || metadata.uid() != Uid::current().as_raw()
+        || metadata.mode() & 0o077 != 0
+    {
+        return Err(format!("Screenshot directory is not private: {}", dir.display()).into());
+    }
+
+    for _ in 0..16 {
+        let suffix = RandomState::new().build_hasher().finish();
+        let path = dir.join(format!("screenshot-{suffix:016x}.png"));
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path);
+        match file {
+            Ok(_) => return Ok(path.to_string_lossy().into_owned()),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    Err("Unable to create a unique screenshot file".into())
 }
 
 fn to_fdo_error(description: &str, err: Box<dyn Error>) -> fdo::Error {
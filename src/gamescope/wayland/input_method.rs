@@ -0,0 +1,99 @@
+use zbus::{dbus_interface, fdo, SignalContext};
+
+use super::manager::{InputMethodCommand, WaylandManager, WaylandMessage};
+
+/// DBus interface implementation for the input method of a Gamescope Wayland
+/// instance. This can be used by on-screen keyboards to type text into the
+/// focused app.
+///
+/// Sending raw key events (E.g. a SendKey method) is not supported, since the
+/// gamescope input method protocol only accepts text and actions. Keys such
+/// as enter, backspace or the arrow keys should be sent with [SetAction]
+/// instead.
+pub struct DBusInterface {
+    path: String,
+    wayland: WaylandManager,
+}
+
+impl DBusInterface {
+    /// Returns a new instance of the InputMethod DBus interface that sends
+    /// requests through the given wayland manager.
+    pub fn new(path: String, wayland: WaylandManager) -> DBusInterface {
+        DBusInterface { path, wayland }
+    }
+
+    /// Sends the given command to the gamescope input method
+    async fn send(&self, command: InputMethodCommand) -> fdo::Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Result<(), String>>(1);
+        self.wayland
+            .send(WaylandMessage::CommandInputMethod(tx, command))
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        match rx.recv().await {
+            Some(Ok(())) => Ok(()),
+            Some(Err(err)) => {
+                log::error!("[{}] Input method error: {err}", self.path);
+                Err(fdo::Error::Failed(err))
+            }
+            None => Err(fdo::Error::Failed(
+                "No response received for input method command".to_owned(),
+            )),
+        }
+    }
+}
+
+#[dbus_interface(name = "org.shadowblip.Gamescope.Wayland.InputMethod")]
+impl DBusInterface {
+    /// Sets the text to insert into the focused app. The text is inserted
+    /// when [Commit] is called.
+    async fn set_text(&self, text: String) -> fdo::Result<()> {
+        self.send(InputMethodCommand::SetString(text)).await
+    }
+
+    /// Sets the action to perform in the focused app when [Commit] is called.
+    /// 0 => none
+    /// 1 => submit
+    /// 2 => delete left
+    /// 3 => delete right
+    /// 4 => move left
+    /// 5 => move right
+    /// 6 => move up
+    /// 7 => move down
+    async fn set_action(&self, action: u32) -> fdo::Result<()> {
+        self.send(InputMethodCommand::SetAction(action)).await
+    }
+
+    /// Applies the text and action set with [SetText] and [SetAction]
+    async fn commit(&self) -> fdo::Result<()> {
+        self.send(InputMethodCommand::Commit).await
+    }
+
+    /// Activates the gamescope input method. Does nothing if it is already
+    /// active. The input method is also activated by any other method call.
+    async fn enable_input_method(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        self.send(InputMethodCommand::Enable).await?;
+        self.input_method_active_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Deactivates the gamescope input method. Does nothing if it is not
+    /// active.
+    async fn disable_input_method(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        self.send(InputMethodCommand::Disable).await?;
+        self.input_method_active_changed(&ctxt).await?;
+        Ok(())
+    }
+
+    /// Whether the gamescope input method is currently active
+    #[dbus_interface(property)]
+    async fn input_method_active(&self) -> bool {
+        self.wayland.input_method_active()
+    }
+}
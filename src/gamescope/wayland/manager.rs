@@ -1,12 +1,73 @@
-use std::{error::Error, os::unix::net::UnixStream};
+use std::{
+    collections::HashMap,
+    error::Error,
+    os::unix::net::UnixStream,
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 use tokio::sync::mpsc::{Receiver, Sender};
-use wayland_client::{protocol::wl_registry, Connection, Dispatch, EventQueue, QueueHandle};
+use wayland_client::{
+    backend::WaylandError,
+    protocol::{wl_registry, wl_seat},
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
 
 use gamescope_wayland_client::{
     control::gamescope_control::{self, GamescopeControl, ScreenshotFlags, ScreenshotType},
-    input_method::gamescope_input_method_manager::{self, GamescopeInputMethodManager},
+    input_method::{
+        gamescope_input_method::{self, GamescopeInputMethod},
+        gamescope_input_method_manager::{self, GamescopeInputMethodManager},
+    },
 };
 
+/// Maximum amount of time to wait for gamescope to report that a screenshot
+/// was taken
+pub const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often to check for a screenshot notification while waiting for one
+const SCREENSHOT_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Number of times to try reconnecting to a wayland socket after the
+/// connection is lost
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Delay before the first reconnect attempt. Doubled after each failed attempt.
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_millis(250);
+
+/// How often an idle connection is checked for having been lost
+const LIVENESS_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Reasons a screenshot request can fail
+#[derive(Clone, Debug)]
+pub enum ScreenshotError {
+    /// Gamescope did not report the screenshot within [SCREENSHOT_TIMEOUT]
+    TimedOut,
+    /// The requested screenshot type is not supported by the running gamescope
+    Unsupported(String),
+    /// The screenshot request could not be made
+    Failed(String),
+}
+
+impl From<String> for ScreenshotError {
+    fn from(err: String) -> Self {
+        ScreenshotError::Failed(err)
+    }
+}
+
+impl std::fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreenshotError::TimedOut => write!(f, "Timed out waiting for screenshot"),
+            ScreenshotError::Unsupported(err) => write!(f, "{err}"),
+            ScreenshotError::Failed(err) => write!(f, "{err}"),
+        }
+    }
+}
+
 pub fn screenshot_type_from_u8(value: u8) -> Option<ScreenshotType> {
     match value {
         0 => Some(ScreenshotType::AllRealLayers),
@@ -17,12 +78,92 @@ pub fn screenshot_type_from_u8(value: u8) -> Option<ScreenshotType> {
     }
 }
 
+/// Version of gamescope_control that added [ScreenshotType::ScreenBuffer]
+const SCREEN_BUFFER_SCREENSHOT_SINCE: u32 = 3;
+
+/// Returns the screenshot types that gamescope_control of the given version
+/// can take. Gamescope does not advertise screenshot types through
+/// feature_support, so they are derived from the bound protocol version
+/// when the control global is bound.
+pub fn supported_screenshot_types(version: u32) -> Vec<ScreenshotType> {
+    if version < gamescope_control::REQ_TAKE_SCREENSHOT_SINCE {
+        return Vec::new();
+    }
+    let mut types = vec![
+        ScreenshotType::AllRealLayers,
+        ScreenshotType::BasePlaneOnly,
+        ScreenshotType::FullComposition,
+    ];
+    if version >= SCREEN_BUFFER_SCREENSHOT_SINCE {
+        types.push(ScreenshotType::ScreenBuffer);
+    }
+    types
+}
+
+/// Returns an error if the given screenshot type is not one of the supported
+/// screenshot types collected from gamescope_control
+pub fn check_screenshot_supported(
+    supported: &[ScreenshotType],
+    screenshot_type: ScreenshotType,
+) -> Result<(), ScreenshotError> {
+    if !supported.contains(&screenshot_type) {
+        return Err(ScreenshotError::Unsupported(format!(
+            "Screenshot type {screenshot_type:?} is not supported by the running gamescope"
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the human-readable name of the given gamescope_control feature id.
+/// Unknown feature ids are rendered as "feature_<id>".
+pub fn feature_name(feature: u32) -> String {
+    match feature {
+        1 => "reshade_shaders".to_string(),
+        2 => "display_info".to_string(),
+        3 => "pixel_filter".to_string(),
+        4 => "refresh_cycle_only_change_refresh_rate".to_string(),
+        5 => "mura_correction".to_string(),
+        _ => format!("feature_{feature}"),
+    }
+}
+
+/// Requests that can be made to the gamescope input method
+#[derive(Clone, Debug)]
+pub enum InputMethodCommand {
+    /// Sets the pending text to insert
+    SetString(String),
+    /// Sets the pending action to perform (E.g. submit, delete left)
+    SetAction(u32),
+    /// Applies the pending text and action
+    Commit,
+    /// Creates the input method if it does not exist yet
+    Enable,
+    /// Destroys the input method if it exists
+    Disable,
+}
+
 /// Enum for internal wayland commands
 /// Values starting with Command will be sent from consuming code and processed in the WaylandManager
 #[derive(Clone, Debug)]
 pub enum WaylandMessage {
-    // Command used to take a screenshot
-    CommandTakeScreenshot(Sender<Result<(), String>>, String, ScreenshotType),
+    // Command used to take a screenshot. Responds with the path gamescope saved it to.
+    CommandTakeScreenshot(
+        Sender<Result<String, ScreenshotError>>,
+        String,
+        ScreenshotType,
+    ),
+    // Command used to wait up to the given duration for gamescope to report a
+    // screenshot taken by any client. The first sender is notified once
+    // waiting has started. Responds with the path gamescope saved it to.
+    CommandWaitForScreenshot(
+        Sender<()>,
+        Sender<Result<String, ScreenshotError>>,
+        Duration,
+    ),
+    // Command used to get the (feature, version, flags) supported by gamescope
+    CommandGetFeatures(Sender<Vec<(u32, u32, u32)>>),
+    // Command used to send a request to the gamescope input method
+    CommandInputMethod(Sender<Result<(), String>>, InputMethodCommand),
 }
 
 // https://github.com/Smithay/wayland-rs/blob/master/wayland-client/examples/simple_window.rs
@@ -30,19 +171,116 @@ pub enum WaylandMessage {
 // This struct represents the state of our app. This simple app does not
 // need any state, by this type still supports the `Dispatch` implementations.
 pub struct WaylandState {
+    socket_path: String,
     control: Option<GamescopeControl>,
     input_method_manager: Option<GamescopeInputMethodManager>,
+    seat: Option<wl_seat::WlSeat>,
+    /// Input method created on first use of the input method manager
+    input_method: Option<GamescopeInputMethod>,
+    /// Serial of the last input method done event, used when committing
+    input_method_serial: u32,
+    /// Mapping of supported feature ids to their (version, flags)
+    features: HashMap<u32, (u32, u32)>,
+    /// Screenshot types supported by the bound gamescope_control
+    screenshot_types: Vec<ScreenshotType>,
+    /// Path of the last screenshot reported by gamescope
+    screenshot_path: Option<String>,
+    /// Set when flushing or dispatching fails, meaning the connection is dead
+    connection_lost: bool,
 }
 
 impl WaylandState {
-    fn new() -> Self {
+    fn new(socket_path: String) -> Self {
         WaylandState {
+            socket_path,
             control: None,
             input_method_manager: None,
+            seat: None,
+            input_method: None,
+            input_method_serial: 0,
+            features: HashMap::new(),
+            screenshot_types: Vec::new(),
+            screenshot_path: None,
+            connection_lost: false,
         }
     }
 }
 
+/// Wayland connection to a gamescope socket along with its event queue and
+/// the state events are dispatched to
+struct WaylandConnection {
+    conn: Connection,
+    event_queue: EventQueue<WaylandState>,
+    qh: QueueHandle<WaylandState>,
+    state: WaylandState,
+}
+
+impl WaylandConnection {
+    /// Connects to the wayland display at the given socket path and binds the
+    /// gamescope globals
+    fn connect(socket_path: &str) -> Result<WaylandConnection, String> {
+        let stream = UnixStream::connect(socket_path).map_err(|err| err.to_string())?;
+        let conn =
+            wayland_client::Connection::from_socket(stream).map_err(|err| err.to_string())?;
+
+        log::info!("Connected to wayland display on: {}", socket_path);
+
+        // Retrieve the WlDisplay Wayland object from the connection. This object is
+        // the starting point of any Wayland program, from which all other objects will
+        // be created.
+        let display = conn.display();
+
+        log::debug!("[{}] Got wayland display: {:?}", socket_path, display);
+
+        // Create an event queue for our event processing
+        let mut event_queue = conn.new_event_queue();
+        // An get its handle to associated new objects to it
+        let qh = event_queue.handle();
+
+        // Create a wl_registry object by sending the wl_display.get_registry request
+        // This method takes two arguments: a handle to the queue the newly created
+        // wl_registry will be assigned to, and the user-data that should be associated
+        // with this registry (here it is () as we don't need user-data).
+        let _registry = display.get_registry(&qh, ());
+
+        // Create state for the application
+        let mut state = WaylandState::new(socket_path.to_string());
+
+        // To actually receive the events, we invoke the `sync_roundtrip` method. This method
+        // is special and you will generally only invoke it during the setup of your program:
+        // it will block until the server has received and processed all the messages you've
+        // sent up to now.
+        //
+        // In our case, that means it'll block until the server has received our
+        // wl_display.get_registry request, and as a reaction has sent us a batch of
+        // wl_registry.global events.
+        //
+        // `sync_roundtrip` will then empty the internal buffer of the queue it has been invoked
+        // on, and thus invoke our `Dispatch` implementation that prints the list of advertized
+        // globals.
+        event_queue
+            .roundtrip(&mut state)
+            .map_err(|err| err.to_string())?;
+
+        // Get initial Wayland result to assign control and input manager
+        let result = event_queue
+            .blocking_dispatch(&mut state)
+            .map_err(|err| err.to_string())?;
+        log::debug!(
+            "[{}] Initial wayland result: {result}, test:{:?}",
+            socket_path,
+            conn.flush()
+        );
+
+        Ok(WaylandConnection {
+            conn,
+            event_queue,
+            qh,
+            state,
+        })
+    }
+}
+
 // Implement `Dispatch<WlRegistry, ()> for out state. This provides the logic
 // to be able to process events for the wl_registry interface.
 //
@@ -73,8 +311,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
         {
             match &interface[..] {
                 "gamescope_control" => {
-                    log::debug!("Found gamescope control interface for Wayland!");
+                    log::debug!(
+                        "[{}] Found gamescope control interface for Wayland!",
+                        state.socket_path
+                    );
                     let control = registry.bind::<GamescopeControl, _, _>(name, version, qh, ());
+                    state.screenshot_types = supported_screenshot_types(control.version());
                     state.control = Some(control);
                 }
                 "gamescope_input_method_manager" => {
@@ -82,6 +324,12 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
                         registry.bind::<GamescopeInputMethodManager, _, _>(name, version, qh, ());
                     state.input_method_manager = Some(input_method_manager);
                 }
+                "wl_seat" => {
+                    if state.seat.is_none() {
+                        let seat = registry.bind::<wl_seat::WlSeat, _, _>(name, 1, qh, ());
+                        state.seat = Some(seat);
+                    }
+                }
                 _ => {}
             }
         }
@@ -91,7 +339,7 @@ impl Dispatch<wl_registry::WlRegistry, ()> for WaylandState {
 /// Handle events going to the [GamescopeControl] object.
 impl Dispatch<GamescopeControl, ()> for WaylandState {
     fn event(
-        _state: &mut Self,
+        state: &mut Self,
         _control: &gamescope_control::GamescopeControl,
         event: gamescope_control::Event,
         _: &(),
@@ -104,10 +352,57 @@ impl Dispatch<GamescopeControl, ()> for WaylandState {
                 version,
                 flags,
             } => {
-                log::debug!("Feature supported: {}, {}, {}", feature, version, flags);
+                log::debug!(
+                    "[{}] Feature supported: {}, {}, {}",
+                    state.socket_path,
+                    feature,
+                    version,
+                    flags
+                );
+                state.features.insert(feature, (version, flags));
             }
             gamescope_control::Event::ScreenshotTaken { path } => {
-                log::info!("Screenshot taken at path: {}", path);
+                log::info!("[{}] Screenshot taken at path: {}", state.socket_path, path);
+                state.screenshot_path = Some(path);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handle events going to the [wl_seat::WlSeat] object.
+impl Dispatch<wl_seat::WlSeat, ()> for WaylandState {
+    fn event(
+        _state: &mut Self,
+        _seat: &wl_seat::WlSeat,
+        _event: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<WaylandState>,
+    ) {
+    }
+}
+
+/// Handle events going to the [GamescopeInputMethod] object.
+impl Dispatch<GamescopeInputMethod, ()> for WaylandState {
+    fn event(
+        state: &mut Self,
+        _input_method: &gamescope_input_method::GamescopeInputMethod,
+        event: gamescope_input_method::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<WaylandState>,
+    ) {
+        match event {
+            gamescope_input_method::Event::Done { serial } => {
+                state.input_method_serial = serial;
+            }
+            gamescope_input_method::Event::Unavailable => {
+                log::warn!(
+                    "[{}] Gamescope input method is unavailable",
+                    state.socket_path
+                );
+                state.input_method = None;
             }
             _ => {}
         }
@@ -127,9 +422,16 @@ impl Dispatch<GamescopeInputMethodManager, ()> for WaylandState {
     }
 }
 
+#[derive(Clone)]
 pub struct WaylandManager {
     command_tx: Sender<WaylandMessage>,
     socket_path: String,
+    /// Whether the gamescope_control global was bound on the current connection
+    control_bound: Arc<AtomicBool>,
+    /// Whether the connection to the wayland display is currently alive
+    connected: Arc<AtomicBool>,
+    /// Whether the gamescope input method currently exists
+    input_method_active: Arc<AtomicBool>,
 }
 
 impl WaylandManager {
@@ -138,6 +440,9 @@ impl WaylandManager {
         let instance = Self {
             command_tx,
             socket_path,
+            control_bound: Arc::new(AtomicBool::new(false)),
+            connected: Arc::new(AtomicBool::new(false)),
+            input_method_active: Arc::new(AtomicBool::new(false)),
         };
 
         instance.run(command_rx).await?;
@@ -146,76 +451,155 @@ impl WaylandManager {
     }
 
     async fn run(&self, mut command_rx: Receiver<WaylandMessage>) -> Result<(), Box<dyn Error>> {
-        let stream = UnixStream::connect(&self.socket_path)?;
-        let conn = wayland_client::Connection::from_socket(stream)?;
-
-        log::info!("Connected to wayland display on: {}", self.socket_path);
+        let mut wayland = WaylandConnection::connect(&self.socket_path)?;
+        self.connected.store(true, Ordering::SeqCst);
 
-        // Retrieve the WlDisplay Wayland object from the connection. This object is
-        // the starting point of any Wayland program, from which all other objects will
-        // be created.
-        let display = conn.display();
-
-        log::debug!("Got wayland display: {:?}", display);
-
-        // Create an event queue for our event processing
-        let mut event_queue = conn.new_event_queue();
-        // An get its handle to associated new objects to it
-        let qh = event_queue.handle();
-
-        // Create a wl_registry object by sending the wl_display.get_registry request
-        // This method takes two arguments: a handle to the queue the newly created
-        // wl_registry will be assigned to, and the user-data that should be associated
-        // with this registry (here it is () as we don't need user-data).
-        let _registry = display.get_registry(&qh, ());
-
-        // Create state for the application
-        let mut state = WaylandState::new();
-
-        // To actually receive the events, we invoke the `sync_roundtrip` method. This method
-        // is special and you will generally only invoke it during the setup of your program:
-        // it will block until the server has received and processed all the messages you've
-        // sent up to now.
-        //
-        // In our case, that means it'll block until the server has received our
-        // wl_display.get_registry request, and as a reaction has sent us a batch of
-        // wl_registry.global events.
-        //
-        // `sync_roundtrip` will then empty the internal buffer of the queue it has been invoked
-        // on, and thus invoke our `Dispatch` implementation that prints the list of advertized
-        // globals.
-        event_queue.roundtrip(&mut state)?;
-
-        // Get initial Wayland result to assign control and input manager
-        let result = event_queue.blocking_dispatch(&mut state)?;
-        log::debug!("Initial wayland result: {result}, test:{:?}", conn.flush());
+        Self::update_control_bound(&self.socket_path, &wayland, &self.control_bound);
 
         let socket_path = self.socket_path.clone();
+        let control_bound = self.control_bound.clone();
+        let connected = self.connected.clone();
+        let input_method_active = self.input_method_active.clone();
 
         // Run loop to listen for commands
         tokio::task::spawn(async move {
-            while let Some(message) = command_rx.recv().await {
-                log::debug!("Wayland Message: {:?}", message);
+            let mut liveness = tokio::time::interval(LIVENESS_CHECK_INTERVAL);
+            loop {
+                // Check idle connections so a lost connection is reported
+                // without waiting for the next command
+                let message = tokio::select! {
+                    message = command_rx.recv() => message,
+                    _ = liveness.tick() => {
+                        if !wayland.state.connection_lost {
+                            let _ = Self::dispatch_nonblocking(
+                                &wayland.conn,
+                                &mut wayland.event_queue,
+                                &mut wayland.state,
+                            );
+                            if wayland.state.connection_lost {
+                                log::warn!("[{socket_path}] Lost connection to wayland display");
+                                connected.store(false, Ordering::SeqCst);
+                            }
+                        }
+                        continue;
+                    }
+                };
+                let Some(message) = message else {
+                    break;
+                };
+                log::debug!("[{socket_path}] Wayland Message: {:?}", message);
+
+                // Try to restore a lost connection before processing the command
+                if wayland.state.connection_lost {
+                    connected.store(false, Ordering::SeqCst);
+                    if let Some(new_connection) = Self::reconnect(&socket_path).await {
+                        wayland = new_connection;
+                        Self::update_control_bound(&socket_path, &wayland, &control_bound);
+                        connected.store(true, Ordering::SeqCst);
+                    }
+                }
 
                 let res: Result<(), Box<dyn Error>> = {
                     match message.clone() {
                         WaylandMessage::CommandTakeScreenshot(tx, file_path, screenshot_type) => {
-                            let res = Self::use_state(&mut state, |state| {
-                                log::info!("Taking screenshot of type:{screenshot_type:?} and saving to {file_path}");
-
-                                state.control.as_ref().unwrap().take_screenshot(
-                                    file_path,
-                                    screenshot_type,
-                                    ScreenshotFlags::Dummy,
-                                );
-                                Self::dispatch(&conn, &mut event_queue, state)?;
-
-                                Ok(())
-                            })
-                            .await;
-
+                            let res =
+                                Self::take_screenshot(&mut wayland, file_path, screenshot_type)
+                                    .await;
+                            if let Err(err) = tx.send(res).await {
+                                log::error!("[{socket_path}] Error sending response back during [WaylandMessage::CommandTakeScreenshot], err:{err:?}");
+                            }
+                        }
+                        WaylandMessage::CommandInputMethod(tx, InputMethodCommand::Disable) => {
+                            if let Some(input_method) = wayland.state.input_method.take() {
+                                log::debug!("[{socket_path}] Destroying input method");
+                                input_method.destroy();
+                            }
+                            let res = Self::dispatch_nonblocking(
+                                &wayland.conn,
+                                &mut wayland.event_queue,
+                                &mut wayland.state,
+                            );
+
+                            input_method_active
+                                .store(wayland.state.input_method.is_some(), Ordering::SeqCst);
                             if let Err(err) = tx.send(res).await {
-                                log::error!("Error sending response back during [WaylandMessage::CommandTakeScreenshot], err:{err:?}");
+                                log::error!("[{socket_path}] Error sending response back during [WaylandMessage::CommandInputMethod], err:{err:?}");
+                            }
+                        }
+                        WaylandMessage::CommandInputMethod(tx, command) => {
+                            let res = Self::use_input_method(
+                                &mut wayland.state,
+                                &wayland.qh,
+                                |state, input_method| {
+                                    log::debug!(
+                                        "[{}] Sending input method command: {command:?}",
+                                        state.socket_path
+                                    );
+                                    match command {
+                                        InputMethodCommand::SetString(text) => {
+                                            input_method.set_string(text)
+                                        }
+                                        InputMethodCommand::SetAction(action) => {
+                                            let action =
+                                                gamescope_input_method::Action::try_from(action)
+                                                    .map_err(|_| {
+                                                        format!(
+                                                            "Invalid input method action: {action}"
+                                                        )
+                                                    })?;
+                                            input_method.set_action(action);
+                                        }
+                                        InputMethodCommand::Commit => {
+                                            input_method.commit(state.input_method_serial)
+                                        }
+                                        // Enabling only requires the input method to
+                                        // exist, and disabling is handled above
+                                        InputMethodCommand::Enable
+                                        | InputMethodCommand::Disable => (),
+                                    }
+                                    Ok(())
+                                },
+                            )
+                            .and_then(|_| {
+                                Self::dispatch_nonblocking(
+                                    &wayland.conn,
+                                    &mut wayland.event_queue,
+                                    &mut wayland.state,
+                                )
+                            });
+
+                            input_method_active
+                                .store(wayland.state.input_method.is_some(), Ordering::SeqCst);
+                            if let Err(err) = tx.send(res).await {
+                                log::error!("[{socket_path}] Error sending response back during [WaylandMessage::CommandInputMethod], err:{err:?}");
+                            }
+                        }
+                        WaylandMessage::CommandWaitForScreenshot(armed_tx, tx, timeout) => {
+                            let res =
+                                Self::wait_for_screenshot(&mut wayland, armed_tx, timeout).await;
+                            if let Err(err) = tx.send(res).await {
+                                log::error!("[{socket_path}] Error sending response back during [WaylandMessage::CommandWaitForScreenshot], err:{err:?}");
+                            }
+                        }
+                        WaylandMessage::CommandGetFeatures(tx) => {
+                            // Pick up any features announced since the last dispatch
+                            if let Err(err) = Self::dispatch_nonblocking(
+                                &wayland.conn,
+                                &mut wayland.event_queue,
+                                &mut wayland.state,
+                            ) {
+                                log::warn!("[{socket_path}] Unable to dispatch pending events, err:{err:?}");
+                            }
+                            let mut features: Vec<(u32, u32, u32)> = wayland
+                                .state
+                                .features
+                                .iter()
+                                .map(|(feature, (version, flags))| (*feature, *version, *flags))
+                                .collect();
+                            features.sort();
+
+                            if let Err(err) = tx.send(features).await {
+                                log::error!("[{socket_path}] Error sending response back during [WaylandMessage::CommandGetFeatures], err:{err:?}");
                             }
                         }
                     }
@@ -224,45 +608,264 @@ impl WaylandManager {
                 };
 
                 if let Err(err) = res {
-                    log::error!("Error processing wayland message:{message:?}, err:{err:?}");
+                    log::error!(
+                        "[{socket_path}] Error processing wayland message:{message:?}, err:{err:?}"
+                    );
+                }
+                if wayland.state.connection_lost {
+                    log::warn!("[{socket_path}] Lost connection to wayland display");
+                    connected.store(false, Ordering::SeqCst);
                 }
+                input_method_active.store(wayland.state.input_method.is_some(), Ordering::SeqCst);
             }
 
+            input_method_active.store(false, Ordering::SeqCst);
+            connected.store(false, Ordering::SeqCst);
             log::info!("Finished listening to wayland path:{socket_path}");
         });
 
         Ok(())
     }
 
-    async fn use_state<F>(state: &mut WaylandState, callback: F) -> Result<(), String>
+    /// Records whether the gamescope_control global was bound on the given
+    /// connection
+    fn update_control_bound(
+        socket_path: &str,
+        wayland: &WaylandConnection,
+        control_bound: &AtomicBool,
+    ) {
+        let bound = wayland.state.control.is_some();
+        if !bound {
+            log::warn!("No gamescope control interface found on wayland display: {socket_path}");
+        }
+        control_bound.store(bound, Ordering::SeqCst);
+    }
+
+    /// Returns whether the gamescope input method currently exists. This is
+    /// updated after each command is processed.
+    pub fn input_method_active(&self) -> bool {
+        self.input_method_active.load(Ordering::SeqCst)
+    }
+
+    /// Tries to reconnect to the given socket path, backing off between
+    /// attempts. Gives up right away if the socket no longer exists, since
+    /// gamescope has exited and the Manager will remove this instance.
+    async fn reconnect(socket_path: &str) -> Option<WaylandConnection> {
+        let mut delay = RECONNECT_INITIAL_DELAY;
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            if !Path::new(socket_path).exists() {
+                log::warn!("[{socket_path}] Wayland socket no longer exists. Not reconnecting.");
+                return None;
+            }
+            log::info!("[{socket_path}] Reconnecting to wayland display (attempt {attempt})");
+            match WaylandConnection::connect(socket_path) {
+                Ok(connection) => return Some(connection),
+                Err(err) => {
+                    log::warn!("[{socket_path}] Failed to reconnect to wayland display: {err}")
+                }
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+        log::error!("[{socket_path}] Giving up reconnecting to wayland display");
+        None
+    }
+
+    /// Requests a screenshot from gamescope and waits up to
+    /// [SCREENSHOT_TIMEOUT] for it to report where the screenshot was saved.
+    async fn take_screenshot(
+        wayland: &mut WaylandConnection,
+        file_path: String,
+        screenshot_type: ScreenshotType,
+    ) -> Result<String, ScreenshotError> {
+        if wayland.state.control.is_none() {
+            return Err("No control found".to_owned().into());
+        }
+        check_screenshot_supported(&wayland.state.screenshot_types, screenshot_type)?;
+
+        // Discard notifications of earlier screenshots
+        Self::dispatch_nonblocking(&wayland.conn, &mut wayland.event_queue, &mut wayland.state)?;
+        wayland.state.screenshot_path = None;
+
+        log::info!(
+            "[{}] Taking screenshot of type:{screenshot_type:?} and saving to {file_path}",
+            wayland.state.socket_path
+        );
+        if let Some(control) = wayland.state.control.as_ref() {
+            control.take_screenshot(file_path, screenshot_type, ScreenshotFlags::Dummy);
+        }
+
+        Self::poll_for_screenshot(wayland, SCREENSHOT_TIMEOUT).await
+    }
+
+    /// Waits up to the given timeout for gamescope to report that a screenshot
+    /// was taken and returns its path. Notifications that arrived before
+    /// waiting started are discarded, and `armed_tx` is notified once they
+    /// have been so the caller can request the screenshot.
+    async fn wait_for_screenshot(
+        wayland: &mut WaylandConnection,
+        armed_tx: Sender<()>,
+        timeout: Duration,
+    ) -> Result<String, ScreenshotError> {
+        if wayland.state.control.is_none() {
+            return Err("No control found".to_owned().into());
+        }
+        Self::dispatch_nonblocking(&wayland.conn, &mut wayland.event_queue, &mut wayland.state)?;
+        wayland.state.screenshot_path = None;
+        let _ = armed_tx.send(()).await;
+
+        Self::poll_for_screenshot(wayland, timeout).await
+    }
+
+    /// Polls for a screenshot notification until one arrives or the timeout
+    /// passes. Events are dispatched without blocking so a request gamescope
+    /// never answers times out instead of stalling the command loop.
+    async fn poll_for_screenshot(
+        wayland: &mut WaylandConnection,
+        timeout: Duration,
+    ) -> Result<String, ScreenshotError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(path) = wayland.state.screenshot_path.take() {
+                return Ok(path);
+            }
+            if Instant::now() >= deadline {
+                return Err(ScreenshotError::TimedOut);
+            }
+            tokio::time::sleep(SCREENSHOT_POLL_INTERVAL).await;
+            Self::dispatch_nonblocking(
+                &wayland.conn,
+                &mut wayland.event_queue,
+                &mut wayland.state,
+            )?;
+        }
+    }
+
+    /// Calls the given callback with the gamescope input method, creating it
+    /// if it does not exist yet.
+    fn use_input_method<F>(
+        state: &mut WaylandState,
+        qh: &QueueHandle<WaylandState>,
+        callback: F,
+    ) -> Result<(), String>
     where
-        F: FnOnce(&mut WaylandState) -> Result<(), String>,
+        F: FnOnce(&WaylandState, &GamescopeInputMethod) -> Result<(), String>,
     {
-        if state.control.is_none() {
-            return Err("No control found".to_owned());
+        if state.input_method.is_none() {
+            let Some(manager) = state.input_method_manager.as_ref() else {
+                return Err(
+                    "Gamescope input method is not supported by the running gamescope".to_owned(),
+                );
+            };
+            let Some(seat) = state.seat.as_ref() else {
+                return Err("No seat found for the gamescope input method".to_owned());
+            };
+            state.input_method = Some(manager.create_input_method(seat, qh, ()));
         }
 
-        callback(state)?;
+        let input_method = state.input_method.as_ref().unwrap();
+        callback(state, input_method)
+    }
 
-        Ok(())
+    /// Flushes pending requests and dispatches any events that have already
+    /// arrived without waiting for new ones.
+    fn dispatch_nonblocking(
+        conn: &Connection,
+        event_queue: &mut EventQueue<WaylandState>,
+        state: &mut WaylandState,
+    ) -> Result<(), String> {
+        let result = Self::try_dispatch_nonblocking(conn, event_queue, state);
+        if result.is_err() {
+            state.connection_lost = true;
+        }
+        result
     }
 
-    fn dispatch(
+    fn try_dispatch_nonblocking(
         conn: &Connection,
         event_queue: &mut EventQueue<WaylandState>,
         state: &mut WaylandState,
-    ) -> Result<usize, String> {
-        conn.flush().map_err(|err| {
-            log::error!("Could not flush wayland queue, err:{err:?}");
-            err.to_string()
-        })?;
-        event_queue.blocking_dispatch(state).map_err(|err| {
-            log::error!("Could not dispatch pending events, err:{err:?}");
-            err.to_string()
-        })
+    ) -> Result<(), String> {
+        conn.flush().map_err(|err| err.to_string())?;
+        if let Some(guard) = event_queue.prepare_read() {
+            match guard.read() {
+                Ok(_) => {}
+                Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(err.to_string()),
+            }
+        }
+        event_queue
+            .dispatch_pending(state)
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    /// Returns whether the gamescope_control global was bound on this socket.
+    /// This is updated whenever the connection is re-established.
+    pub fn control_bound(&self) -> bool {
+        self.control_bound.load(Ordering::SeqCst)
+    }
+
+    /// Returns whether the connection to the wayland display is alive. This is
+    /// false while reconnecting and after reconnecting has given up.
+    pub fn connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
     }
 
     pub async fn send(&self, msg: WaylandMessage) -> Result<(), Box<dyn Error>> {
         Ok(self.command_tx.send(msg).await?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn screenshot_types_map_from_u8() {
+        assert_eq!(
+            screenshot_type_from_u8(0),
+            Some(ScreenshotType::AllRealLayers)
+        );
+        assert_eq!(
+            screenshot_type_from_u8(1),
+            Some(ScreenshotType::BasePlaneOnly)
+        );
+        assert_eq!(
+            screenshot_type_from_u8(2),
+            Some(ScreenshotType::FullComposition)
+        );
+        assert_eq!(
+            screenshot_type_from_u8(3),
+            Some(ScreenshotType::ScreenBuffer)
+        );
+        assert_eq!(screenshot_type_from_u8(4), None);
+    }
+
+    #[test]
+    fn screenshots_are_unsupported_before_take_screenshot() {
+        let version = gamescope_control::REQ_TAKE_SCREENSHOT_SINCE - 1;
+        assert!(supported_screenshot_types(version).is_empty());
+    }
+
+    #[test]
+    fn screen_buffer_screenshots_require_newer_control() {
+        let supported = supported_screenshot_types(SCREEN_BUFFER_SCREENSHOT_SINCE - 1);
+        let result = check_screenshot_supported(&supported, ScreenshotType::ScreenBuffer);
+        assert!(matches!(result, Err(ScreenshotError::Unsupported(_))));
+
+        let supported = supported_screenshot_types(SCREEN_BUFFER_SCREENSHOT_SINCE);
+        for value in 0..=3 {
+            let screenshot_type = screenshot_type_from_u8(value).unwrap();
+            assert!(check_screenshot_supported(&supported, screenshot_type).is_ok());
+        }
+    }
+
+    #[test]
+    fn screenshot_types_missing_from_supported_set_fail() {
+        let supported = [ScreenshotType::AllRealLayers, ScreenshotType::BasePlaneOnly];
+        let result = check_screenshot_supported(&supported, ScreenshotType::FullComposition);
+        assert!(matches!(result, Err(ScreenshotError::Unsupported(_))));
+        assert!(check_screenshot_supported(&supported, ScreenshotType::BasePlaneOnly).is_ok());
+    }
+}
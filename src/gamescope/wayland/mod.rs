@@ -1,2 +1,3 @@
 pub mod dbus;
+pub mod input_method;
 pub mod manager;
@@ -0,0 +1,513 @@
+use std::{
+    error::Error,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use x11rb::{
+    connection::Connection,
+    errors::ReplyError,
+    protocol::{
+        randr::{self, ConnectionExt as _},
+        xproto::{
+            Atom, AtomEnum, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt,
+            EventMask, GetPropertyReply, MapState, PropMode, Window,
+        },
+        ErrorKind, Event,
+    },
+    rust_connection::RustConnection,
+};
+
+/// Current mode of the display output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputMode {
+    pub width: u32,
+    pub height: u32,
+    /// Refresh rate in Hz, or 0 if unknown
+    pub refresh: u32,
+}
+
+/// How often a geometry listener checks for new events and whether it has
+/// been stopped
+const GEOMETRY_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Position and size of a window reported by a ConfigureNotify event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Geometry {
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Raw X11 connection to an XWayland display, used for requests that are not
+/// provided by [gamescope_x11_client::xwayland::XWayland].
+pub struct X11Client {
+    conn: RustConnection,
+    root: Window,
+}
+
+impl X11Client {
+    /// Connects to the X display with the given name (E.g. ":0", ":1")
+    pub fn connect(name: &str) -> Result<X11Client, Box<dyn Error>> {
+        let (conn, screen_num) = x11rb::connect(Some(name))?;
+        let root = conn.setup().roots[screen_num].root;
+        Ok(X11Client { conn, root })
+    }
+
+    /// Starts a new thread with its own connection to the given X display that
+    /// listens for position and size changes of the given window. Changes are
+    /// sent to the returned receiver until the returned flag is set, the
+    /// receiver is dropped, or the window is destroyed.
+    pub fn listen_for_geometry_changes(
+        name: &str,
+        window_id: u32,
+    ) -> Result<(Arc<AtomicBool>, Receiver<Geometry>), Box<dyn Error>> {
+        let client = X11Client::connect(name)?;
+        let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::STRUCTURE_NOTIFY);
+        client
+            .conn
+            .change_window_attributes(window_id, &attributes)?
+            .check()?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut last_geometry = None;
+            while !stopped.load(Ordering::SeqCst) {
+                let event = match client.conn.poll_for_event() {
+                    Ok(Some(event)) => event,
+                    Ok(None) => {
+                        thread::sleep(GEOMETRY_POLL_INTERVAL);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to read events for window {window_id}: {e}");
+                        break;
+                    }
+                };
+                match event {
+                    Event::ConfigureNotify(event) if event.window == window_id => {
+                        let geometry = Geometry {
+                            x: event.x,
+                            y: event.y,
+                            width: event.width,
+                            height: event.height,
+                        };
+                        // Stacking changes also generate events, so only
+                        // send actual geometry changes
+                        if last_geometry == Some(geometry) {
+                            continue;
+                        }
+                        last_geometry = Some(geometry);
+                        if tx.send(geometry).is_err() {
+                            break;
+                        }
+                    }
+                    Event::DestroyNotify(event) if event.window == window_id => break,
+                    _ => {}
+                }
+            }
+        });
+
+        Ok((stop, rx))
+    }
+
+    /// Returns whether the connection to the X server is still alive. This
+    /// makes a round trip to the server.
+    pub fn is_connected(&self) -> bool {
+        self.conn
+            .get_input_focus()
+            .map(|cookie| cookie.reply().is_ok())
+            .unwrap_or(false)
+    }
+
+    /// Returns the root window ID of the display
+    pub fn get_root_window_id(&self) -> u32 {
+        self.root
+    }
+
+    /// Returns the parent of the given window, or None if the window is the
+    /// root window.
+    pub fn get_window_parent(&self, window_id: u32) -> Result<Option<u32>, Box<dyn Error>> {
+        let reply = self.conn.query_tree(window_id)?.reply()?;
+        if reply.parent == x11rb::NONE {
+            return Ok(None);
+        }
+        Ok(Some(reply.parent))
+    }
+
+    /// Returns the children of the given window in stacking order, from the
+    /// bottom-most window first to the top-most window last.
+    pub fn get_window_stack(&self, window_id: u32) -> Result<Vec<u32>, Box<dyn Error>> {
+        let reply = self.conn.query_tree(window_id)?.reply()?;
+        Ok(reply.children)
+    }
+
+    /// Returns the top-level window (direct child of the root window) that
+    /// contains the given window.
+    pub fn get_toplevel_window(&self, window_id: u32) -> Result<u32, Box<dyn Error>> {
+        let mut window_id = window_id;
+        while let Some(parent) = self.get_window_parent(window_id)? {
+            if parent == self.root {
+                break;
+            }
+            window_id = parent;
+        }
+        Ok(window_id)
+    }
+
+    /// Returns whether or not the given window exists
+    pub fn window_exists(&self, window_id: u32) -> Result<bool, Box<dyn Error>> {
+        match self.conn.get_window_attributes(window_id)?.reply() {
+            Ok(_) => Ok(true),
+            Err(ReplyError::X11Error(err)) if err.error_kind == ErrorKind::Window => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Returns whether or not the given window is mapped and viewable
+    pub fn is_window_viewable(&self, window_id: u32) -> Result<bool, Box<dyn Error>> {
+        let reply = self.conn.get_window_attributes(window_id)?.reply()?;
+        Ok(reply.map_state == MapState::VIEWABLE)
+    }
+
+    /// Moves and resizes the given window. Returns an error if the window
+    /// does not exist or the size is zero.
+    pub fn set_window_geometry(
+        &self,
+        window_id: u32,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+    ) -> Result<(), Box<dyn Error>> {
+        if width == 0 || height == 0 {
+            return Err(format!("Invalid window size {width}x{height}").into());
+        }
+        match self.conn.get_geometry(window_id)?.reply() {
+            Ok(_) => (),
+            Err(ReplyError::X11Error(err))
+                if matches!(err.error_kind, ErrorKind::Window | ErrorKind::Drawable) =>
+            {
+                return Err(format!("Window {window_id} does not exist").into());
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let aux = ConfigureWindowAux::new()
+            .x(x as i32)
+            .y(y as i32)
+            .width(width as u32)
+            .height(height as u32);
+        self.conn.configure_window(window_id, &aux)?.check()?;
+        Ok(())
+    }
+
+    /// Returns the current mode of the display output. The mode of the first
+    /// active RandR CRTC is used. If RandR is unavailable, the size of the
+    /// root window is returned with an unknown refresh rate.
+    pub fn get_output_mode(&self) -> Result<OutputMode, Box<dyn Error>> {
+        if self
+            .conn
+            .extension_information(randr::X11_EXTENSION_NAME)?
+            .is_none()
+        {
+            let geometry = self.conn.get_geometry(self.root)?.reply()?;
+            return Ok(OutputMode {
+                width: geometry.width as u32,
+                height: geometry.height as u32,
+                refresh: 0,
+            });
+        }
+
+        let resources = self
+            .conn
+            .randr_get_screen_resources_current(self.root)?
+            .reply()?;
+        for crtc in resources.crtcs.iter() {
+            let info = self
+                .conn
+                .randr_get_crtc_info(*crtc, resources.config_timestamp)?
+                .reply()?;
+            if info.mode == x11rb::NONE {
+                continue;
+            }
+            let Some(mode) = resources.modes.iter().find(|mode| mode.id == info.mode) else {
+                continue;
+            };
+            let total = mode.htotal as u64 * mode.vtotal as u64;
+            let refresh = if total == 0 {
+                0
+            } else {
+                ((mode.dot_clock as u64 + total / 2) / total) as u32
+            };
+            return Ok(OutputMode {
+                width: info.width as u32,
+                height: info.height as u32,
+                refresh,
+            });
+        }
+
+        Ok(OutputMode::default())
+    }
+
+    /// Returns the atom with the given name, or None if the atom has not been
+    /// interned on the X server.
+    pub fn get_atom(&self, name: &str) -> Result<Option<Atom>, Box<dyn Error>> {
+        let reply = self.conn.intern_atom(true, name.as_bytes())?.reply()?;
+        if reply.atom == u32::from(AtomEnum::NONE) {
+            return Ok(None);
+        }
+        Ok(Some(reply.atom))
+    }
+
+    /// Returns the atom with the given name, interning it if it does not exist
+    fn intern_atom(&self, name: &str) -> Result<Atom, Box<dyn Error>> {
+        Ok(self.conn.intern_atom(false, name.as_bytes())?.reply()?.atom)
+    }
+
+    /// Returns the CARDINAL values of the property with the given name on the
+    /// given window, or None if the property is not set.
+    pub fn get_cardinal_property(
+        &self,
+        window_id: u32,
+        name: &str,
+    ) -> Result<Option<Vec<u32>>, Box<dyn Error>> {
+        let Some(atom) = self.get_atom(name)? else {
+            return Ok(None);
+        };
+        let reply = self
+            .conn
+            .get_property(false, window_id, atom, AtomEnum::CARDINAL, 0, u32::MAX)?
+            .reply()?;
+        let Some(values) = reply.value32() else {
+            return Ok(None);
+        };
+        let values: Vec<u32> = values.collect();
+        if values.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(values))
+    }
+
+    /// Sets the property with the given name on the given window to the given
+    /// CARDINAL values.
+    pub fn set_cardinal_property(
+        &self,
+        window_id: u32,
+        name: &str,
+        values: &[u32],
+    ) -> Result<(), Box<dyn Error>> {
+        let atom = self.intern_atom(name)?;
+        self.conn
+            .change_property32(
+                PropMode::REPLACE,
+                window_id,
+                atom,
+                AtomEnum::CARDINAL,
+                values,
+            )?
+            .check()?;
+        Ok(())
+    }
+
+    /// Sets the CARDINAL values of several properties on the given window.
+    /// All atoms are interned first, then every change is sent in a single
+    /// flush so the X server receives them together.
+    pub fn set_cardinal_properties(
+        &self,
+        window_id: u32,
+        properties: &[(&str, &[u32])],
+    ) -> Result<(), Box<dyn Error>> {
+        let atoms = properties
+            .iter()
+            .map(|(name, _)| self.intern_atom(name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut cookies = Vec::with_capacity(properties.len());
+        for (atom, (_, values)) in atoms.into_iter().zip(properties) {
+            let cookie = self.conn.change_property32(
+                PropMode::REPLACE,
+                window_id,
+                atom,
+                AtomEnum::CARDINAL,
+                values,
+            )?;
+            cookies.push(cookie);
+        }
+        self.conn.flush()?;
+        for cookie in cookies {
+            cookie.check()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of the property with the given name on the given
+    /// window as a string, or None if the property is not set. CARDINAL values
+    /// are returned as a comma-separated list and STRING/UTF8_STRING values
+    /// are returned as-is.
+    pub fn get_property(
+        &self,
+        window_id: u32,
+        name: &str,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let Some(atom) = self.get_atom(name)? else {
+            return Ok(None);
+        };
+        let Some(reply) = self.get_raw_property(window_id, atom)? else {
+            return Ok(None);
+        };
+        if reply.type_ == u32::from(AtomEnum::CARDINAL) {
+            let Some(values) = reply.value32() else {
+                return Err(format!("Property {name} has an invalid CARDINAL format").into());
+            };
+            let values: Vec<String> = values.map(|value| value.to_string()).collect();
+            return Ok(Some(values.join(",")));
+        }
+        if self.is_string_type(reply.type_)? {
+            let value = String::from_utf8_lossy(&reply.value);
+            return Ok(Some(value.trim_end_matches('\0').to_string()));
+        }
+        Err(format!("Property {name} has an unsupported type").into())
+    }
+
+    /// Sets the property with the given name on the given window from a
+    /// string, using the given property type. Supported types are CARDINAL,
+    /// whose value is a comma-separated list of numbers, and STRING or
+    /// UTF8_STRING, whose value is written as-is.
+    pub fn set_property(
+        &self,
+        window_id: u32,
+        name: &str,
+        type_name: &str,
+        value: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.window_exists(window_id)? {
+            return Err(format!("Window {window_id} does not exist").into());
+        }
+        match type_name {
+            "CARDINAL" => {
+                let values: Option<Vec<u32>> = value
+                    .split(',')
+                    .map(|part| part.trim().parse().ok())
+                    .collect();
+                let Some(values) = values else {
+                    return Err(format!("Property {name} requires CARDINAL values").into());
+                };
+                return self.set_cardinal_property(window_id, name, &values);
+            }
+            "STRING" | "UTF8_STRING" => (),
+            _ => return Err(format!("Unsupported property type: {type_name}").into()),
+        }
+
+        let atom = self.intern_atom(name)?;
+        let type_ = self.intern_atom(type_name)?;
+        self.conn
+            .change_property8(PropMode::REPLACE, window_id, atom, type_, value.as_bytes())?
+            .check()?;
+        Ok(())
+    }
+
+    /// Returns the names of all properties set on the given window
+    pub fn list_property_names(&self, window_id: u32) -> Result<Vec<String>, Box<dyn Error>> {
+        let atoms = self.conn.list_properties(window_id)?.reply()?.atoms;
+        let cookies = atoms
+            .into_iter()
+            .map(|atom| self.conn.get_atom_name(atom))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut names = Vec::with_capacity(cookies.len());
+        for cookie in cookies {
+            names.push(String::from_utf8_lossy(&cookie.reply()?.name).into_owned());
+        }
+        Ok(names)
+    }
+
+    /// Returns the (instance, class) from the WM_CLASS property of the given
+    /// window, or None if the property is not set.
+    pub fn get_wm_class(&self, window_id: u32) -> Result<Option<(String, String)>, Box<dyn Error>> {
+        let Some(reply) = self.get_raw_property(window_id, AtomEnum::WM_CLASS.into())? else {
+            return Ok(None);
+        };
+        let value = String::from_utf8_lossy(&reply.value);
+        let mut parts = value.split('\0');
+        let instance = parts.next().unwrap_or_default().to_string();
+        let class = parts.next().unwrap_or_default().to_string();
+        Ok(Some((instance, class)))
+    }
+
+    /// Returns whether or not the given window accepts input focus according
+    /// to ICCCM. Windows accept focus if the input field of WM_HINTS is set or
+    /// if WM_TAKE_FOCUS is listed in WM_PROTOCOLS. Windows without WM_HINTS
+    /// are assumed to accept focus.
+    pub fn accepts_input_focus(&self, window_id: u32) -> Result<bool, Box<dyn Error>> {
+        // Bit in the WM_HINTS flags indicating the input field is set
+        const INPUT_HINT: u32 = 1;
+
+        let Some(reply) = self.get_raw_property(window_id, AtomEnum::WM_HINTS.into())? else {
+            return Ok(true);
+        };
+        let hints: Vec<u32> = reply.value32().map(|v| v.collect()).unwrap_or_default();
+        let flags = hints.first().copied().unwrap_or_default();
+        if flags & INPUT_HINT == 0 {
+            return Ok(true);
+        }
+        if hints.get(1).copied().unwrap_or_default() != 0 {
+            return Ok(true);
+        }
+
+        // Globally active windows take focus through WM_TAKE_FOCUS
+        let (Some(protocols), Some(take_focus)) = (
+            self.get_atom("WM_PROTOCOLS")?,
+            self.get_atom("WM_TAKE_FOCUS")?,
+        ) else {
+            return Ok(false);
+        };
+        let Some(reply) = self.get_raw_property(window_id, protocols)? else {
+            return Ok(false);
+        };
+        let accepts = reply
+            .value32()
+            .map(|mut atoms| atoms.any(|atom| atom == take_focus))
+            .unwrap_or(false);
+        Ok(accepts)
+    }
+
+    /// Returns the raw property reply for the given atom on the given window,
+    /// or None if the property is not set.
+    fn get_raw_property(
+        &self,
+        window_id: u32,
+        atom: Atom,
+    ) -> Result<Option<GetPropertyReply>, Box<dyn Error>> {
+        let reply = self
+            .conn
+            .get_property(false, window_id, atom, AtomEnum::ANY, 0, u32::MAX)?
+            .reply();
+        let reply = match reply {
+            Ok(reply) => reply,
+            Err(ReplyError::X11Error(err)) if err.error_kind == ErrorKind::Window => {
+                return Err(format!("Window {window_id} does not exist").into());
+            }
+            Err(err) => return Err(err.into()),
+        };
+        if reply.type_ == u32::from(AtomEnum::NONE) {
+            return Ok(None);
+        }
+        Ok(Some(reply))
+    }
+
+    /// Returns whether or not the given property type is STRING or UTF8_STRING
+    fn is_string_type(&self, type_: Atom) -> Result<bool, Box<dyn Error>> {
+        if type_ == u32::from(AtomEnum::STRING) {
+            return Ok(true);
+        }
+        Ok(self.get_atom("UTF8_STRING")? == Some(type_))
+    }
+}
@@ -2,12 +2,88 @@ use gamescope_x11_client::{
     atoms::GamescopeAtom,
     xwayland::{BlurMode, Primary, WindowLifecycleEvent, XWayland},
 };
-use std::{collections::HashMap, error::Error, sync::mpsc::Receiver};
-use tokio::task::AbortHandle;
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    env,
+    error::Error,
+    fmt::{Arguments, Debug},
+    fs,
+    os::unix::net::UnixStream,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::AbortHandle,
+};
 use zbus::{fdo, zvariant::Type, Connection, SignalContext};
 use zbus_macros::dbus_interface;
 
-#[derive(Type, serde::Serialize)]
+use super::{
+    manager::Command,
+    wayland::manager::{ScreenshotError, WaylandMessage},
+    x11::{OutputMode, X11Client},
+};
+
+/// Maximum number of window titles to remember for each watched window
+const WINDOW_NAME_HISTORY_SIZE: usize = 16;
+
+/// Amount of time PID and window lookups are cached for
+const WINDOW_CACHE_TTL: Duration = Duration::from_millis(500);
+
+/// Cache of lookup results keyed by window or process ID, along with the time
+/// each result was computed
+type LookupCache = Mutex<HashMap<u32, (Instant, Vec<u32>)>>;
+
+/// Root window atom used to set the Gamescope upscale filter
+const SCALING_FILTER_ATOM: &str = "GAMESCOPE_NEW_SCALING_FILTER";
+
+/// Names of the Gamescope upscale filters, indexed by their atom value
+const SCALING_FILTER_NAMES: [&str; 5] = ["linear", "nearest", "fsr", "nis", "pixel"];
+
+/// Root window atom used to set how Gamescope scales apps to the output
+const SCALING_MODE_ATOM: &str = "GAMESCOPE_NEW_SCALING_SCALER";
+
+/// Names of the Gamescope scaling modes, indexed by their atom value
+const SCALING_MODE_NAMES: [&str; 5] = ["auto", "integer", "fit", "fill", "stretch"];
+
+/// Root window atom used to set the Gamescope upscale filter sharpness
+const SHARPNESS_ATOM: &str = "GAMESCOPE_SHARPNESS";
+
+/// Maximum value of [SHARPNESS_ATOM]
+const SHARPNESS_MAX: u32 = 20;
+
+/// Root window atom used to enable HDR output in Gamescope
+pub const HDR_ENABLED_ATOM: &str = "GAMESCOPE_DISPLAY_HDR_ENABLED";
+
+/// Amount of time focus must stay unchanged before [AppFocusChanged] is emitted
+const APP_FOCUS_DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Longest time [WaitForFocusableApp] will wait for an app
+const FOCUSABLE_APP_MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Longest time [RequestScreenshotBlocking] will wait for a screenshot
+const SCREENSHOT_MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Gamescope atoms that are read from the root window by [DumpRootState]
+const ROOT_STATE_ATOMS: [GamescopeAtom; 9] = [
+    GamescopeAtom::BaselayerAppId,
+    GamescopeAtom::BaselayerWindow,
+    GamescopeAtom::FocusedApp,
+    GamescopeAtom::FocusedAppGFX,
+    GamescopeAtom::FocusedWindow,
+    GamescopeAtom::FocusableApps,
+    GamescopeAtom::FocusableWindows,
+    GamescopeAtom::BlurRadius,
+    GamescopeAtom::AllowTearing,
+];
+
+#[derive(Debug, Type, serde::Serialize)]
 pub struct WindowGeometry {
     pub width: u16,
     pub height: u16,
@@ -15,13 +91,144 @@ pub struct WindowGeometry {
     pub y: i16,
 }
 
+/// Delay before retrying a failed reconnect. Doubled after each failure.
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(250);
+
+/// Default maximum delay between reconnect attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Tracks automatic reconnects to the XWayland server so that only one runs
+/// at a time and failed attempts back off exponentially
+struct ReconnectState {
+    in_flight: AtomicBool,
+    /// Earliest time the next attempt may start, and the delay to wait after
+    /// the next failure
+    backoff: Mutex<(Option<Instant>, Duration)>,
+    max_delay: Duration,
+}
+
+impl Default for ReconnectState {
+    fn default() -> Self {
+        let max_delay = env::var("GAMESCOPE_DBUS_RECONNECT_MAX_DELAY_MS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(RECONNECT_MAX_DELAY);
+        Self::new(max_delay)
+    }
+}
+
+impl ReconnectState {
+    /// Returns a new state whose backoff never exceeds the given delay
+    fn new(max_delay: Duration) -> Self {
+        Self {
+            in_flight: AtomicBool::new(false),
+            backoff: Mutex::new((None, RECONNECT_MIN_DELAY)),
+            max_delay,
+        }
+    }
+
+    /// Returns true if a reconnect attempt may start now. The caller must
+    /// call [ReconnectState::finish] once the attempt completes.
+    fn try_start(&self) -> bool {
+        let (next_attempt, _) = *self.backoff.lock().unwrap();
+        if next_attempt.is_some_and(|next_attempt| Instant::now() < next_attempt) {
+            return false;
+        }
+        self.in_flight
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    /// Marks the current attempt as finished, resetting the backoff on
+    /// success or increasing it on failure
+    fn finish(&self, success: bool) {
+        let mut backoff = self.backoff.lock().unwrap();
+        if success {
+            *backoff = (None, RECONNECT_MIN_DELAY);
+        } else {
+            let delay = backoff.1;
+            *backoff = (
+                Some(Instant::now() + delay),
+                (delay * 2).min(self.max_delay),
+            );
+        }
+        self.in_flight.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Counters of an XWayland interface over its lifetime, returned as JSON by
+/// [Diagnostics]
+#[derive(Debug, Default)]
+struct InterfaceDiagnostics {
+    /// Number of times reconnecting to the XWayland server was attempted
+    reconnect_attempts: AtomicU64,
+    /// Number of method calls and property reads that returned an error
+    failed_calls: AtomicU64,
+    /// Number of properties of watched windows that could not be read
+    failed_property_reads: AtomicU64,
+    /// Number of signals emitted by the interface
+    signals_dispatched: AtomicU64,
+}
+
+impl InterfaceDiagnostics {
+    /// Increments the given counter
+    fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the counters as a JSON object
+    fn to_json(&self) -> String {
+        serde_json::json!({
+            "reconnect_attempts": self.reconnect_attempts.load(Ordering::Relaxed),
+            "failed_calls": self.failed_calls.load(Ordering::Relaxed),
+            "failed_property_reads": self.failed_property_reads.load(Ordering::Relaxed),
+            "signals_dispatched": self.signals_dispatched.load(Ordering::Relaxed),
+        })
+        .to_string()
+    }
+}
+
+/// Snapshot of a window returned as JSON by [DumpWindowInfo]
+#[derive(Debug, serde::Serialize)]
+struct WindowInfo {
+    window_id: u32,
+    name: Option<String>,
+    /// (instance, class) from WM_CLASS
+    wm_class: Option<(String, String)>,
+    geometry: WindowGeometry,
+    app_id: Option<u32>,
+    pids: Vec<u32>,
+    children: Vec<u32>,
+    /// Values of all GAMESCOPE_* and STEAM_* properties set on the window
+    properties: BTreeMap<String, String>,
+}
+
 /// DBus interface implementation for Gamescope XWayland instance.
 pub struct DBusInterface {
     path: String,
     xwayland: XWayland,
+    x11: X11Client,
     dbus: Connection,
     watched_windows: Vec<u32>,
     watch_handles: HashMap<u32, AbortHandle>,
+    /// Client provided labels for watched windows
+    watch_labels: HashMap<u32, String>,
+    /// Recently observed titles of watched windows, oldest first
+    window_name_history: Mutex<HashMap<u32, VecDeque<String>>>,
+    /// Whether or not method calls and their results are logged
+    trace: bool,
+    /// Stop flags of the geometry listeners of windows watched with
+    /// [WatchWindowGeometry]
+    geometry_watches: HashMap<u32, Arc<AtomicBool>>,
+    /// Backoff state of automatic reconnects
+    reconnect_state: ReconnectState,
+    /// Cached results of [GetWindowsForPid]
+    pid_windows_cache: LookupCache,
+    /// Cached results of [GetPidsForWindow]
+    window_pids_cache: LookupCache,
+    /// Counters returned by [Diagnostics]
+    diagnostics: Arc<InterfaceDiagnostics>,
 }
 
 impl DBusInterface {
@@ -34,13 +241,23 @@ impl DBusInterface {
     ) -> Result<DBusInterface, Box<dyn Error>> {
         let mut xwayland = XWayland::new(name.clone());
         xwayland.connect()?;
+        let x11 = X11Client::connect(&name)?;
         let watched_windows = Vec::new();
         Ok(DBusInterface {
             path,
             xwayland,
+            x11,
             watched_windows,
             dbus,
             watch_handles: HashMap::new(),
+            watch_labels: HashMap::new(),
+            window_name_history: Mutex::new(HashMap::new()),
+            trace: false,
+            geometry_watches: HashMap::new(),
+            reconnect_state: ReconnectState::default(),
+            pid_windows_cache: Mutex::new(HashMap::new()),
+            window_pids_cache: Mutex::new(HashMap::new()),
+            diagnostics: Arc::new(InterfaceDiagnostics::default()),
         })
     }
 
@@ -53,30 +270,309 @@ impl DBusInterface {
             .await
     }
 
-    /// Tries to ensure that the backing X11 connection is valid.
+    /// Returns whether both the XWayland and X11 connections are alive
+    fn is_connected(&self) -> bool {
+        self.xwayland.is_connected() && self.x11.is_connected()
+    }
+
+    /// Tries to ensure that the backing X11 connection is valid. Only one
+    /// reconnect runs at a time, and failed reconnects are retried with
+    /// exponential backoff.
     async fn ensure_connected(&self) {
-        if self.xwayland.is_connected() {
+        if self.is_connected() {
             return;
         }
-        log::warn!("Lost connection to XWayland server. Reconnecting.");
-        let iface_ref = self.get_interface().await;
-        if iface_ref.is_err() {
+        if !self.reconnect_state.try_start() {
             return;
         }
+        log::warn!(
+            "[{}] Lost connection to XWayland server. Reconnecting.",
+            self.path
+        );
+        let Ok(iface_ref) = self.get_interface().await else {
+            self.reconnect_state.finish(false);
+            return;
+        };
+        let path = self.path.clone();
         tokio::task::spawn(async move {
-            log::info!("Trying to reconnect to XWayland server.");
-            let interface_ref = &iface_ref.unwrap();
-            let mut iface = interface_ref.get_mut().await;
-            if iface.xwayland.is_connected() {
+            let mut iface = iface_ref.get_mut().await;
+            if iface.is_connected() {
+                iface.reconnect_state.finish(true);
                 return;
             }
-            if let Err(e) = iface.xwayland.connect() {
-                log::warn!("Failed to reconnect to XWayland server: {:?}", e)
-            }
-            log::info!("Successfully reconnected to XWayland server.");
+            // Let clients see that a reconnect is in progress
+            iface
+                .emit_connected_changed(iface_ref.signal_context())
+                .await;
+
+            let success = {
+                let iface = &mut *iface;
+                InterfaceDiagnostics::increment(&iface.diagnostics.reconnect_attempts);
+                reconnect(&path, &mut iface.xwayland, &mut iface.x11)
+            };
+            iface.reconnect_state.finish(success);
+            iface
+                .emit_connected_changed(iface_ref.signal_context())
+                .await;
         });
     }
 
+    /// Emits a change of the [Connected] property
+    async fn emit_connected_changed(&self, ctxt: &SignalContext<'_>) {
+        match self.connected_changed(ctxt).await {
+            Ok(()) => InterfaceDiagnostics::increment(&self.diagnostics.signals_dispatched),
+            Err(error) => log::warn!(
+                "[{}] Unable to signal connection change: {:?}",
+                self.path,
+                error
+            ),
+        }
+    }
+
+    /// Reads the current title of the given window and adds it to the title
+    /// history if it differs from the last observed title.
+    fn record_window_name(&self, window_id: u32) {
+        let name = match self.xwayland.get_window_name(window_id) {
+            Ok(name) => name.unwrap_or_default(),
+            Err(e) => {
+                log::debug!(
+                    "[{}] Unable to read name of window {}: {:?}",
+                    self.path,
+                    window_id,
+                    e
+                );
+                return;
+            }
+        };
+        let mut history = self.window_name_history.lock().unwrap();
+        let titles = history.entry(window_id).or_default();
+        if titles.back() == Some(&name) {
+            return;
+        }
+        if titles.len() >= WINDOW_NAME_HISTORY_SIZE {
+            titles.pop_front();
+        }
+        titles.push_back(name);
+    }
+
+    /// Logs the given method call and its result if tracing is enabled
+    fn trace<T: Debug>(&self, call: Arguments, result: &fdo::Result<T>) {
+        if result.is_err() {
+            InterfaceDiagnostics::increment(&self.diagnostics.failed_calls);
+        }
+        if !self.trace {
+            return;
+        }
+        log::debug!("[{}] {} -> {:?}", self.path, call, result);
+    }
+
+    /// Clears all cached PID and window lookups
+    fn invalidate_window_cache(&self) {
+        self.pid_windows_cache.lock().unwrap().clear();
+        self.window_pids_cache.lock().unwrap().clear();
+    }
+
+    /// Starts listening for property changes on the given window, dispatching
+    /// them as [WindowPropertyChanged] signals. Does nothing if the window is
+    /// already being watched.
+    fn start_window_watch(&mut self, window_id: u32, label: String) -> fdo::Result<()> {
+        // If the window is already being watched, do nothing
+        if self.watched_windows.contains(&window_id) {
+            return Ok(());
+        }
+
+        // Spawn a new thread to listen for property changes for the given window
+        let (_, rx) = self
+            .xwayland
+            .listen_for_window_property_changes(window_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        // Create a closure to run whenever a property changes on this window
+        let dispatch_to_dbus = |conn: Connection, path: String, event: String, id: u32| {
+            tokio::task::spawn(async move {
+                // Get the object instance at the given path so we can send DBus signal
+                // updates
+                let Ok(iface_ref) = conn
+                    .object_server()
+                    .interface::<_, DBusInterface>(path.clone())
+                    .await
+                else {
+                    log::warn!(
+                        "[{}] Not able to find dbus interface when watching window",
+                        path
+                    );
+                    return;
+                };
+
+                // log::trace!("Got property change event: {:?}", event);
+
+                // Keep track of title changes for the window and read the new
+                // value of the property
+                let mut app_id = None;
+                let (value, deleted, diagnostics) = {
+                    let iface = iface_ref.get().await;
+                    let diagnostics = iface.diagnostics.clone();
+                    if event == "WM_NAME" || event == "_NET_WM_NAME" {
+                        iface.record_window_name(id);
+                    }
+                    if event == GamescopeAtom::SteamGame.to_string() {
+                        app_id = iface.xwayland.get_app_id(id).ok().flatten();
+                    }
+                    match iface.x11.get_property(id, &event) {
+                        Ok(Some(value)) => (value, false, diagnostics),
+                        Ok(None) => (String::new(), true, diagnostics),
+                        Err(e) => {
+                            log::debug!(
+                                "[{}] Unable to read property {} on window {}: {:?}",
+                                path,
+                                event,
+                                id,
+                                e
+                            );
+                            InterfaceDiagnostics::increment(&diagnostics.failed_property_reads);
+                            (String::new(), false, diagnostics)
+                        }
+                    }
+                };
+                let signal_dispatched =
+                    || InterfaceDiagnostics::increment(&diagnostics.signals_dispatched);
+
+                // Emit the property changed signals for this window
+                DBusInterface::window_property_changed(
+                    iface_ref.signal_context(),
+                    id,
+                    event.clone(),
+                )
+                .await
+                .map(|_| signal_dispatched())
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+                DBusInterface::window_property_changed_value(
+                    iface_ref.signal_context(),
+                    id,
+                    event,
+                    value,
+                    deleted,
+                )
+                .await
+                .map(|_| signal_dispatched())
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+                if let Some(app_id) = app_id {
+                    DBusInterface::app_id_assigned(iface_ref.signal_context(), id, app_id)
+                        .await
+                        .map(|_| signal_dispatched())
+                        .unwrap_or_else(|error| {
+                            log::warn!("[{}] Unable to signal app id change: {:?}", path, error)
+                        });
+                }
+            });
+        };
+
+        // Spawn a task to process the messages in the receiver
+        let conn = self.dbus.clone();
+        let path = self.path.clone();
+        let watch_label = label.clone();
+        let handle = tokio::task::spawn_blocking(move || {
+            log::debug!(
+                "[{}] Started listening for property changes for window {} (label: {:?})",
+                path,
+                window_id,
+                watch_label
+            );
+
+            // Wait for events from the channel and dispatch them to the DBus interface
+            while let Ok(event) = rx.recv() {
+                // log::trace!("Got property change event: {:?}", event);
+                dispatch_to_dbus(conn.clone(), path.clone(), event, window_id);
+            }
+            log::warn!(
+                "[{}] Stopped listening for property changes for window {} (label: {:?})",
+                path,
+                window_id,
+                watch_label
+            );
+        })
+        .abort_handle();
+
+        // Add to the list of windows we're watching
+        self.watched_windows.push(window_id);
+        self.watch_handles.insert(window_id, handle);
+        if !label.is_empty() {
+            self.watch_labels.insert(window_id, label);
+        }
+        self.record_window_name(window_id);
+
+        Ok(())
+    }
+
+    /// Gathers the [WindowInfo] of the given window. Errors only if the
+    /// window geometry cannot be read, which means the window does not exist.
+    fn window_info(&self, window_id: u32) -> fdo::Result<WindowInfo> {
+        let geometry = self
+            .xwayland
+            .get_geometry_for_window(window_id)
+            .map(|geometry| WindowGeometry {
+                width: geometry.width,
+                height: geometry.height,
+                x: geometry.x,
+                y: geometry.y,
+            })
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        let mut properties = BTreeMap::new();
+        let names = self.x11.list_property_names(window_id).unwrap_or_default();
+        for name in names {
+            if !name.starts_with("GAMESCOPE") && !name.starts_with("STEAM") {
+                continue;
+            }
+            if let Ok(Some(value)) = self.x11.get_property(window_id, &name) {
+                properties.insert(name, value);
+            }
+        }
+
+        Ok(WindowInfo {
+            window_id,
+            name: self.xwayland.get_window_name(window_id).ok().flatten(),
+            wm_class: self.x11.get_wm_class(window_id).ok().flatten(),
+            geometry,
+            app_id: self.xwayland.get_app_id(window_id).ok().flatten(),
+            pids: self
+                .xwayland
+                .get_pids_for_window(window_id)
+                .unwrap_or_default(),
+            children: self
+                .xwayland
+                .get_window_children(window_id)
+                .unwrap_or_default(),
+            properties,
+        })
+    }
+
+    /// Stops listening for property changes on the given window. Does nothing
+    /// if the window is not being watched.
+    fn stop_window_watch(&mut self, window_id: u32) {
+        let index = self.watched_windows.iter().position(|x| *x == window_id);
+        if index.is_none() {
+            return;
+        }
+
+        // Remove the element and stop watching
+        self.watched_windows.remove(index.unwrap());
+        let handle = self.watch_handles.remove(&window_id).unwrap();
+        handle.abort();
+        self.window_name_history.lock().unwrap().remove(&window_id);
+        let label = self.watch_labels.remove(&window_id).unwrap_or_default();
+        log::debug!(
+            "[{}] Stopped watching window {} (label: {:?})",
+            self.path,
+            window_id,
+            label
+        );
+    }
+
     /// Starts a new thread listening for window lifecycle events. Returns
     /// a receiver channel where changes will be sent to. This is usually used
     /// to process DBus property changes outside of the dispatched handler
@@ -97,26 +593,51 @@ impl DBusInterface {
         Ok(self.xwayland.get_name())
     }
 
+    /// The display number of the X display (E.g. 1 for ":1")
+    #[dbus_interface(property)]
+    async fn display_number(&self) -> fdo::Result<u32> {
+        let name = self.xwayland.get_name();
+        parse_display_number(&name).ok_or_else(|| {
+            fdo::Error::Failed(format!("Unable to parse display number from name: {name}"))
+        })
+    }
+
     /// Returns true if this instance is the primary Gamescope xwayland instance
     #[dbus_interface(property)]
     pub async fn primary(&self) -> fdo::Result<bool> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .is_primary_instance()
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("primary()"), &result);
+        result
+    }
+
+    /// Whether the connection to the XWayland server is alive
+    #[dbus_interface(property)]
+    async fn connected(&self) -> bool {
+        self.is_connected()
+    }
+
+    /// JSON object with counters of reconnect attempts, failed calls, failed
+    /// reads of watched window properties and dispatched signals since this
+    /// instance was created. Useful to attach to bug reports.
+    #[dbus_interface(property)]
+    async fn diagnostics(&self) -> String {
+        self.diagnostics.to_json()
     }
 
     /// Returns the root window ID of the xwayland instance
     #[dbus_interface(property)]
     async fn root_window_id(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .get_root_window_id()
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("root_window_id()"), &result);
+        result
     }
 
     /// List of windows currently being watched for property changes. The
@@ -127,6 +648,17 @@ impl DBusInterface {
         self.watched_windows.clone()
     }
 
+    /// List of windows currently being watched for property changes along
+    /// with the label they were watched with. Windows watched without a label
+    /// have an empty label.
+    #[dbus_interface(property)]
+    async fn watched_windows_detailed(&self) -> Vec<(u32, String)> {
+        self.watched_windows
+            .iter()
+            .map(|id| (*id, self.watch_labels.get(id).cloned().unwrap_or_default()))
+            .collect()
+    }
+
     /// Emitted when a window property changes on a watched window.
     #[dbus_interface(signal)]
     async fn window_property_changed(
@@ -135,190 +667,599 @@ impl DBusInterface {
         prop: String,
     ) -> zbus::Result<()>;
 
+    /// Emitted along with [WindowPropertyChanged] with the value of the
+    /// property read at the time of the change, formatted like
+    /// [GetWindowProperty]. If the property was deleted, the value is empty
+    /// and `deleted` is true. The value is also empty if the property has an
+    /// unsupported type.
+    #[dbus_interface(signal)]
+    async fn window_property_changed_value(
+        ctxt: &SignalContext<'_>,
+        window: u32,
+        prop: String,
+        value: String,
+        deleted: bool,
+    ) -> zbus::Result<()>;
+
+    /// Emitted when the app id (STEAM_GAME) of a watched window is set or
+    /// changes. Clients can use this instead of polling [HasAppId].
+    #[dbus_interface(signal)]
+    async fn app_id_assigned(
+        ctxt: &SignalContext<'_>,
+        window_id: u32,
+        app_id: u32,
+    ) -> zbus::Result<()>;
+
     /// Start watching the given window. The [WindowPropertyChanged] signal
     /// will fire whenever a window property changes on the window. Use
     /// [UnwatchWindow] to stop watching the given window.
     async fn watch_window(&mut self, window_id: u32) -> fdo::Result<()> {
+        self.watch_window_labeled(window_id, String::new()).await
+    }
+
+    /// Start watching the given window like [WatchWindow], attaching the given
+    /// label to the watch. The label is included in log messages about the
+    /// watch and listed in [WatchedWindowsDetailed].
+    async fn watch_window_labeled(&mut self, window_id: u32, label: String) -> fdo::Result<()> {
         self.ensure_connected().await;
-        // If the window is already being watched, do nothing
-        if self.watched_windows.contains(&window_id) {
+        self.start_window_watch(window_id, label)
+    }
+
+    /// Start watching each of the given windows like [WatchWindow]. Windows
+    /// that could not be watched are skipped and returned, while the rest of
+    /// the batch is still watched.
+    async fn watch_windows(&mut self, window_ids: Vec<u32>) -> Vec<u32> {
+        self.ensure_connected().await;
+        let mut failed = Vec::new();
+        for window_id in window_ids {
+            if let Err(e) = self.start_window_watch(window_id, String::new()) {
+                log::warn!(
+                    "[{}] Unable to watch window {}: {:?}",
+                    self.path,
+                    window_id,
+                    e
+                );
+                failed.push(window_id);
+            }
+        }
+        failed
+    }
+
+    /// Stop watching the given window. The [WindowPropertyChanged] signal will
+    /// no longer fire for the given window.
+    async fn unwatch_window(&mut self, window_id: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.stop_window_watch(window_id);
+        Ok(())
+    }
+
+    /// Start watching the position and size of the given window. The
+    /// [WindowGeometryChanged] signal will fire whenever it changes. This is
+    /// independent of [WatchWindow].
+    async fn watch_window_geometry(&mut self, window_id: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if self.geometry_watches.contains_key(&window_id) {
             return Ok(());
         }
+        let (stop, rx) =
+            X11Client::listen_for_geometry_changes(&self.xwayland.get_name(), window_id)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
 
-        // Spawn a new thread to listen for property changes for the given window
-        let (_, rx) = self
-            .xwayland
-            .listen_for_window_property_changes(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let conn = self.dbus.clone();
+        let path = self.path.clone();
+        let diagnostics = self.diagnostics.clone();
+        let watch = stop.clone();
+        tokio::task::spawn_blocking(move || {
+            while let Ok(geometry) = rx.recv() {
+                let conn = conn.clone();
+                let path = path.clone();
+                let diagnostics = diagnostics.clone();
+                tokio::task::spawn(async move {
+                    let Ok(iface_ref) = conn
+                        .object_server()
+                        .interface::<_, DBusInterface>(path.clone())
+                        .await
+                    else {
+                        return;
+                    };
+                    let geometry = WindowGeometry {
+                        width: geometry.width,
+                        height: geometry.height,
+                        x: geometry.x,
+                        y: geometry.y,
+                    };
+                    DBusInterface::window_geometry_changed(
+                        iface_ref.signal_context(),
+                        window_id,
+                        geometry,
+                    )
+                    .await
+                    .map(|_| InterfaceDiagnostics::increment(&diagnostics.signals_dispatched))
+                    .unwrap_or_else(|error| {
+                        log::warn!("[{}] Unable to signal geometry change: {:?}", path, error)
+                    });
+                });
+            }
+            log::debug!("Stopped listening for geometry changes for window {window_id}");
 
-        // Create a closure to run whenever a property changes on this window
-        let dispatch_to_dbus = |conn: Connection, path: String, event: String, id: u32| {
+            // The listener stops once the window is destroyed, so remove the
+            // watch unless it was already replaced by a newer one.
+            watch.store(true, Ordering::SeqCst);
             tokio::task::spawn(async move {
-                // Get the object instance at the given path so we can send DBus signal
-                // updates
                 let Ok(iface_ref) = conn
                     .object_server()
-                    .interface::<_, DBusInterface>(path)
+                    .interface::<_, DBusInterface>(path.clone())
                     .await
                 else {
-                    log::warn!("Not able to find dbus interface when watching window");
                     return;
                 };
-
-                // log::trace!("Got property change event: {:?}", event);
-
-                // Emit the property changed signal for this window
-                DBusInterface::window_property_changed(iface_ref.signal_context(), id, event)
-                    .await
-                    .unwrap_or_else(|error| {
-                        log::warn!("Unable to signal value change: {:?}", error)
-                    });
+                let mut iface = iface_ref.get_mut().await;
+                if iface
+                    .geometry_watches
+                    .get(&window_id)
+                    .is_some_and(|current| Arc::ptr_eq(current, &watch))
+                {
+                    iface.geometry_watches.remove(&window_id);
+                }
             });
-        };
-
-        // Spawn a task to process the messages in the receiver
-        let conn = self.dbus.clone();
-        let path = self.path.clone();
-        let handle = tokio::task::spawn_blocking(move || {
-            log::debug!(
-                "Started listening for property changes for window {}",
-                window_id
-            );
-
-            // Wait for events from the channel and dispatch them to the DBus interface
-            while let Ok(event) = rx.recv() {
-                // log::trace!("Got property change event: {:?}", event);
-                dispatch_to_dbus(conn.clone(), path.clone(), event, window_id);
-            }
-            log::warn!("Stopped listening for property changes");
-        })
-        .abort_handle();
+        });
+        self.geometry_watches.insert(window_id, stop);
 
-        // Add to the list of windows we're watching
-        self.watched_windows.push(window_id);
-        self.watch_handles.insert(window_id, handle);
+        Ok(())
+    }
 
+    /// Stop watching the position and size of the given window. This does
+    /// not affect property watches started with [WatchWindow].
+    async fn unwatch_window_geometry(&mut self, window_id: u32) -> fdo::Result<()> {
+        if let Some(stop) = self.geometry_watches.remove(&window_id) {
+            stop.store(true, Ordering::SeqCst);
+        }
         Ok(())
     }
 
-    /// Stop watching the given window. The [WindowPropertyChanged] signal will
-    /// no longer fire for the given window.
-    async fn unwatch_window(&mut self, window_id: u32) -> fdo::Result<()> {
+    /// Emitted when the position or size of a window watched with
+    /// [WatchWindowGeometry] changes
+    #[dbus_interface(signal)]
+    async fn window_geometry_changed(
+        ctxt: &SignalContext<'_>,
+        window_id: u32,
+        geometry: WindowGeometry,
+    ) -> zbus::Result<()>;
+
+    /// Stop watching each of the given windows like [UnwatchWindow]. Windows
+    /// that are not being watched are ignored.
+    async fn unwatch_windows(&mut self, window_ids: Vec<u32>) -> fdo::Result<()> {
         self.ensure_connected().await;
-        let index = self.watched_windows.iter().position(|x| *x == window_id);
-        if index.is_none() {
-            return Ok(());
+        for window_id in window_ids {
+            self.stop_window_watch(window_id);
         }
+        Ok(())
+    }
+
+    /// Returns up to `max` of the most recently observed titles of the given
+    /// watched window, oldest first. Returns an empty list if the window is
+    /// not being watched.
+    async fn get_window_name_history(&self, window_id: u32, max: u32) -> Vec<String> {
+        let history = self.window_name_history.lock().unwrap();
+        let Some(titles) = history.get(&window_id) else {
+            return Vec::new();
+        };
+        let skip = titles.len().saturating_sub(max as usize);
+        titles.iter().skip(skip).cloned().collect()
+    }
+
+    /// Enables or disables logging of every method call on this instance
+    /// along with its result at the debug log level. Useful for diagnosing a
+    /// single misbehaving display without tracing every instance.
+    async fn set_x11_trace(&mut self, enabled: bool) {
+        self.trace = enabled;
+        log::info!(
+            "[{}] {} method call tracing",
+            self.path,
+            if enabled { "Enabled" } else { "Disabled" }
+        );
+    }
 
-        // Remove the element and stop watching
-        self.watched_windows.remove(index.unwrap());
-        let handle = self.watch_handles.remove(&window_id).unwrap();
-        handle.abort();
+    /// Forces a new connection to the XWayland server, even if the current
+    /// connection appears valid. Returns whether or not reconnecting
+    /// succeeded. Concurrent calls are serialized, so only one reconnect is
+    /// attempted at a time.
+    async fn reconnect(
+        &mut self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<bool> {
+        InterfaceDiagnostics::increment(&self.diagnostics.reconnect_attempts);
+        let success = reconnect(&self.path, &mut self.xwayland, &mut self.x11);
+        if success {
+            self.reconnect_state.finish(true);
+        }
+        self.emit_connected_changed(&ctxt).await;
+        Ok(success)
+    }
 
-        Ok(())
+    /// Returns an approximate count of the signal subscriptions on this
+    /// instance. Services cannot see the match rules clients register with
+    /// the bus, so this is the number of active window watches, each of which
+    /// drives [WindowPropertyChanged] signals. Clients that only listen for
+    /// [WindowLifecycle] or property change signals are not counted, and a
+    /// window watched on behalf of several clients is counted once.
+    async fn signal_subscriber_count(&self) -> u32 {
+        self.watch_handles.len() as u32
     }
 
     /// Discover the process IDs that are associated with the given window
     async fn get_pids_for_window(&self, window_id: u32) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
-        let value = self
-            .xwayland
-            .get_pids_for_window(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+        let result = cached_lookup(&self.window_pids_cache, window_id, || {
+            self.xwayland.get_pids_for_window(window_id)
+        })
+        .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_pids_for_window({window_id})"), &result);
+        result
     }
 
     /// Returns the window id(s) for the given process ID.
     async fn get_windows_for_pid(&self, pid: u32) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
-        let value = self
+        let result = cached_lookup(&self.pid_windows_cache, pid, || {
+            self.xwayland.get_windows_for_pid(pid)
+        })
+        .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_windows_for_pid({pid})"), &result);
+        result
+    }
+
+    /// Returns the app ID for the given process ID. Each window owned by the
+    /// process, along with its top-level window, is checked in turn and the
+    /// first non-zero app ID is returned. This handles apps where only one of
+    /// their windows has the STEAM_GAME property set. Returns 0 if none of the
+    /// windows have an app ID.
+    async fn get_app_id_for_pid(&self, pid: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let windows = cached_lookup(&self.pid_windows_cache, pid, || {
+            self.xwayland.get_windows_for_pid(pid)
+        })
+        .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        let result = find_app_id(
+            &windows,
+            |window_id| self.x11.get_toplevel_window(window_id).ok(),
+            |window_id| self.xwayland.get_app_id(window_id),
+        )
+        .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_app_id_for_pid({pid})"), &result);
+        result
+    }
+
+    /// Returns the top-level window (direct child of the root window) for the
+    /// given process ID. Each window owned by the process is resolved to its
+    /// top-level window, and only viewable top-level windows are considered.
+    /// A window with an app ID is preferred, otherwise the first viewable
+    /// window is returned. Returns 0 if no window qualifies.
+    async fn get_toplevel_window_for_pid(&self, pid: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let windows = self
             .xwayland
             .get_windows_for_pid(pid)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+
+        let mut candidates = Vec::new();
+        for window_id in windows {
+            let toplevel = self
+                .x11
+                .get_toplevel_window(window_id)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if candidates.contains(&toplevel) {
+                continue;
+            }
+            let viewable = self
+                .x11
+                .is_window_viewable(toplevel)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if viewable {
+                candidates.push(toplevel);
+            }
+        }
+
+        for window_id in candidates.iter() {
+            let has_app_id = self
+                .xwayland
+                .has_app_id(*window_id)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if has_app_id {
+                self.trace(
+                    format_args!("get_toplevel_window_for_pid({pid})"),
+                    &Ok(*window_id),
+                );
+                return Ok(*window_id);
+            }
+        }
+
+        let window_id = candidates.first().copied().unwrap_or_default();
+        self.trace(
+            format_args!("get_toplevel_window_for_pid({pid})"),
+            &Ok(window_id),
+        );
+        Ok(window_id)
+    }
+
+    /// Clears the cached results of [GetWindowsForPid] and [GetPidsForWindow]
+    /// so the next lookup walks the window tree again. Cached results are
+    /// otherwise kept for 500ms, or until a window is created or destroyed.
+    async fn clear_window_cache(&self) {
+        self.invalidate_window_cache();
     }
 
     /// Returns the window name of the given window
     async fn get_window_name(&self, window_id: u32) -> fdo::Result<String> {
         self.ensure_connected().await;
-        let name = self
+        let result = self
             .xwayland
             .get_window_name(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(name.unwrap_or_default())
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_name({window_id})"), &result);
+        Ok(result?.unwrap_or_default())
+    }
+
+    /// Returns the instance and class names from the WM_CLASS property of the
+    /// given window. Returns empty strings if the property is not set.
+    async fn get_window_class(&self, window_id: u32) -> fdo::Result<(String, String)> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .get_wm_class(window_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_class({window_id})"), &result);
+        Ok(result?.unwrap_or_default())
+    }
+
+    /// Returns whether or not the given window accepts input focus based on
+    /// its ICCCM WM_HINTS input flag and WM_TAKE_FOCUS protocol. Returns true
+    /// if the window does not set WM_HINTS, following ICCCM conventions.
+    async fn window_accepts_focus(&self, window_id: u32) -> fdo::Result<bool> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .accepts_input_focus(window_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("window_accepts_focus({window_id})"), &result);
+        result
     }
 
     /// Returns the width, height, x, and y of the window
     async fn get_geometry_for_window(&self, window_id: u32) -> fdo::Result<WindowGeometry> {
         self.ensure_connected().await;
-        let geometry = self
+        let result = self
             .xwayland
             .get_geometry_for_window(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(WindowGeometry {
-            width: geometry.width,
-            height: geometry.height,
-            x: geometry.x,
-            y: geometry.y,
-        })
+            .map(|geometry| WindowGeometry {
+                width: geometry.width,
+                height: geometry.height,
+                x: geometry.x,
+                y: geometry.y,
+            })
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(
+            format_args!("get_geometry_for_window({window_id})"),
+            &result,
+        );
+        result
     }
 
     /// Returns the window ids of the children of the given window
     async fn get_window_children(&self, window_id: u32) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .get_window_children(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_children({window_id})"), &result);
+        result
+    }
+
+    /// Returns a JSON object describing the given window, including its name,
+    /// WM_CLASS, geometry, app id, PIDs, children and the values of all
+    /// gamescope and Steam properties set on it. Fields that cannot be read
+    /// are null or empty.
+    async fn dump_window_info(&self, window_id: u32) -> fdo::Result<String> {
+        self.ensure_connected().await;
+        let result = self.window_info(window_id).and_then(|info| {
+            serde_json::to_string(&info).map_err(|err| fdo::Error::Failed(err.to_string()))
+        });
+        self.trace(format_args!("dump_window_info({window_id})"), &result);
+        result
+    }
+
+    /// Returns the direct children of the given window in X11 stacking order.
+    /// The first window is at the bottom of the stack and the last window is
+    /// on top. Unmapped windows are included.
+    async fn get_window_stack(&self, window_id: u32) -> fdo::Result<Vec<u32>> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .get_window_stack(window_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_stack({window_id})"), &result);
+        result
+    }
+
+    /// Moves and resizes the given window within the nested X display.
+    /// Returns an error if the window does not exist.
+    async fn set_window_geometry(
+        &self,
+        window_id: u32,
+        x: i16,
+        y: i16,
+        width: u16,
+        height: u16,
+    ) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .set_window_geometry(window_id, x, y, width, height)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(
+            format_args!("set_window_geometry({window_id}, {x}, {y}, {width}, {height})"),
+            &result,
+        );
+        result
     }
 
     /// Recursively returns all child windows of the given window id
     async fn get_all_windows(&self, window_id: u32) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .get_all_windows(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_all_windows({window_id})"), &result);
+        result
+    }
+
+    /// Returns the number of windows recursively beneath the given window
+    async fn get_window_count(&self, root_window_id: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let result = self
+            .xwayland
+            .get_all_windows(root_window_id)
+            .map(|windows| windows.len() as u32)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_count({root_window_id})"), &result);
+        result
     }
 
     /// Returns the currently set app ID on the given window
     async fn get_app_id(&self, window_id: u32) -> fdo::Result<u32> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .get_app_id(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value.unwrap_or_default())
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_app_id({window_id})"), &result);
+        Ok(result?.unwrap_or_default())
     }
 
     /// Sets the app ID on the given window
     async fn set_app_id(&self, window_id: u32, app_id: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        let result = self
+            .xwayland
             .set_app_id(window_id, app_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(())
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("set_app_id({window_id}, {app_id})"), &result);
+        result
     }
 
     /// Removes the app ID on the given window
     async fn remove_app_id(&self, window_id: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        let result = self
+            .xwayland
             .remove_xprop(window_id, GamescopeAtom::SteamGame)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(())
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("remove_app_id({window_id})"), &result);
+        result
     }
 
     /// Returns whether or not the given window has an app ID set
     async fn has_app_id(&self, window_id: u32) -> fdo::Result<bool> {
         self.ensure_connected().await;
-        let value = self
+        let result = self
             .xwayland
             .has_app_id(window_id)
-            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("has_app_id({window_id})"), &result);
+        result
+    }
+
+    /// Returns the opacity of the given window from 0.0 (transparent) to 1.0
+    /// (opaque). Windows without an opacity set are fully opaque.
+    async fn get_window_opacity(&self, window_id: u32) -> fdo::Result<f64> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .get_cardinal_property(window_id, "_NET_WM_WINDOW_OPACITY")
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("get_window_opacity({window_id})"), &result);
+        let Some(value) = result?.and_then(|values| values.first().copied()) else {
+            return Ok(1.0);
+        };
+        Ok(value as f64 / u32::MAX as f64)
+    }
+
+    /// Sets the opacity of the given window from 0.0 (transparent) to 1.0
+    /// (opaque) using the _NET_WM_WINDOW_OPACITY property. Gamescope may
+    /// ignore the opacity depending on how the window is composited.
+    async fn set_window_opacity(&self, window_id: u32, opacity: f64) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if !(0.0..=1.0).contains(&opacity) {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Opacity must be between 0.0 and 1.0, got {opacity}"
+            )));
+        }
+        let value = (opacity * u32::MAX as f64).round() as u32;
+        let result = self
+            .x11
+            .set_cardinal_property(window_id, "_NET_WM_WINDOW_OPACITY", &[value])
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(
+            format_args!("set_window_opacity({window_id}, {opacity})"),
+            &result,
+        );
+        result
+    }
+
+    /// Returns whether or not the atom with the given name exists on the X
+    /// server. Returns false for atoms that have not been interned.
+    async fn atom_exists(&self, atom_name: String) -> fdo::Result<bool> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .get_atom(&atom_name)
+            .map(|atom| atom.is_some())
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(format_args!("atom_exists({atom_name:?})"), &result);
+        result
+    }
+
+    /// Returns the value of the X11 property with the given name on the given
+    /// window as a string. CARDINAL values are returned as a comma-separated
+    /// list. Returns an empty string if the property is not set.
+    async fn get_window_property(&self, window_id: u32, name: String) -> fdo::Result<String> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .get_property(window_id, &name)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(
+            format_args!("get_window_property({window_id}, {name:?})"),
+            &result,
+        );
+        Ok(result?.unwrap_or_default())
+    }
+
+    /// Sets the X11 property with the given name on the given window. The
+    /// property type must be one of "CARDINAL", "STRING" or "UTF8_STRING".
+    /// CARDINAL values are given as a comma-separated list of numbers.
+    async fn set_window_property(
+        &self,
+        window_id: u32,
+        name: String,
+        property_type: String,
+        value: String,
+    ) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        let result = self
+            .x11
+            .set_property(window_id, &name, &property_type, &value)
+            .map_err(|err| fdo::Error::Failed(err.to_string()));
+        self.trace(
+            format_args!(
+                "set_window_property({window_id}, {name:?}, {property_type:?}, {value:?})"
+            ),
+            &result,
+        );
+        result
     }
 
     /// Fires when a new window is lifecycle
@@ -335,7 +1276,35 @@ impl DBusInterface {
 pub struct DBusInterfacePrimary {
     dbus: Connection,
     path: String,
-    xwayland: XWayland,
+    /// Locked so reconnecting can replace the connections without taking the
+    /// interface write lock, which long-running waits would otherwise block
+    xwayland: RwLock<XWayland>,
+    x11: RwLock<X11Client>,
+    /// Timers used to clear notifications set with [show_notification],
+    /// keyed by window id along with the generation that started them
+    notification_timers: Mutex<HashMap<u32, (u64, AbortHandle)>>,
+    /// Incremented every time a notification timer is started, used to tell
+    /// whether a timer has been superseded by a newer one
+    notification_generation: AtomicU64,
+    /// Unix time in milliseconds when each root window atom was last observed
+    /// changing, keyed by atom name
+    property_change_times: Mutex<HashMap<String, u64>>,
+    /// Number of times each app id was focused along with the unix time in
+    /// milliseconds it was last focused
+    app_usage: Mutex<HashMap<u32, (u32, u64)>>,
+    /// Incremented on every focus change, used to debounce [AppFocusChanged]
+    app_focus_generation: AtomicU64,
+    /// Backoff state of automatic reconnects
+    reconnect_state: ReconnectState,
+    /// Transmit side of the [super::manager::Manager] command channel
+    manager_tx: mpsc::Sender<Command>,
+    /// Whether [GetRootAtom] and [SetRootAtom] reject atoms that are not
+    /// already interned on the X server
+    strict_atoms: AtomicBool,
+    /// Notified whenever the focusable apps change, used by
+    /// [WaitForFocusableApp]. Shared with the property change listener so it
+    /// can notify waiters without locking the interface.
+    focusable_apps_changes: Arc<watch::Sender<()>>,
 }
 
 impl DBusInterfacePrimary {
@@ -345,25 +1314,86 @@ impl DBusInterfacePrimary {
         name: String,
         path: String,
         dbus: Connection,
+        manager_tx: mpsc::Sender<Command>,
     ) -> Result<DBusInterfacePrimary, Box<dyn Error>> {
-        let mut xwayland = XWayland::new(name);
+        let mut xwayland = XWayland::new(name.clone());
         xwayland.connect()?;
+        let x11 = X11Client::connect(&name)?;
 
         Ok(DBusInterfacePrimary {
-            xwayland,
+            xwayland: RwLock::new(xwayland),
+            x11: RwLock::new(x11),
             dbus,
             path,
+            notification_timers: Mutex::new(HashMap::new()),
+            notification_generation: AtomicU64::new(0),
+            property_change_times: Mutex::new(HashMap::new()),
+            app_usage: Mutex::new(HashMap::new()),
+            app_focus_generation: AtomicU64::new(0),
+            reconnect_state: ReconnectState::default(),
+            manager_tx,
+            strict_atoms: AtomicBool::new(true),
+            focusable_apps_changes: Arc::new(watch::Sender::new(())),
         })
     }
 
+    /// Returns the current XWayland connection
+    fn xwayland(&self) -> RwLockReadGuard<'_, XWayland> {
+        self.xwayland.read().unwrap()
+    }
+
+    /// Returns the current X11 connection
+    fn x11(&self) -> RwLockReadGuard<'_, X11Client> {
+        self.x11.read().unwrap()
+    }
+
+    /// Returns whether both the XWayland and X11 connections are alive
+    fn is_connected(&self) -> bool {
+        self.xwayland().is_connected() && self.x11().is_connected()
+    }
+
+    /// Replaces the XWayland and X11 connections with new ones. Both are
+    /// locked for writing, so concurrent reconnects are serialized.
+    fn reconnect_clients(&self) -> bool {
+        let mut xwayland = self.xwayland.write().unwrap();
+        let mut x11 = self.x11.write().unwrap();
+        reconnect(&self.path, &mut xwayland, &mut x11)
+    }
+
     /// Starts a new thread listening for gamescope property changes. Returns
     /// a receiver channel where changes will be sent to. This is usually used
     /// to process DBus property changes outside of the dispatched handler
     pub fn listen_for_property_changes(&self) -> Result<Receiver<String>, Box<dyn Error>> {
-        let (_, rx) = self.xwayland.listen_for_property_changes()?;
+        let (_, rx) = self.xwayland().listen_for_property_changes()?;
         Ok(rx)
     }
 
+    /// Returns the sender notified whenever the focusable apps change
+    pub fn focusable_apps_changes(&self) -> Arc<watch::Sender<()>> {
+        self.focusable_apps_changes.clone()
+    }
+
+    /// Validates an atom name passed to [GetRootAtom] or [SetRootAtom]. In
+    /// strict mode, the atom must already be interned on the X server.
+    fn validate_root_atom(&self, name: &str) -> fdo::Result<()> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_graphic()) {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Invalid atom name: '{name}'"
+            )));
+        }
+        if !self.strict_atoms.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        let atom = self
+            .x11()
+            .get_atom(name)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        if atom.is_none() {
+            return Err(fdo::Error::InvalidArgs(format!("Unknown atom: {name}")));
+        }
+        Ok(())
+    }
+
     /// Starts a new thread listening for window created events. Returns
     /// a receiver channel where changes will be sent to. This is usually used
     /// to process DBus property changes outside of the dispatched handler
@@ -373,7 +1403,7 @@ impl DBusInterfacePrimary {
     )]
     pub fn listen_for_window_created(&self) -> Result<Receiver<u32>, Box<dyn Error>> {
         #[allow(deprecated)]
-        let (_, rx) = self.xwayland.listen_for_window_created()?;
+        let (_, rx) = self.xwayland().listen_for_window_created()?;
         Ok(rx)
     }
 
@@ -386,29 +1416,124 @@ impl DBusInterfacePrimary {
             .await
     }
 
-    /// Tries to ensure that the backing X11 connection is valid.
+    /// Tries to ensure that the backing X11 connection is valid. Only one
+    /// reconnect runs at a time, and failed reconnects are retried with
+    /// exponential backoff.
     async fn ensure_connected(&self) {
-        if self.xwayland.is_connected() {
+        if self.is_connected() {
             return;
         }
-        log::warn!("Lost connection to XWayland server. Reconnecting.");
-        let iface_ref = self.get_interface().await;
-        if iface_ref.is_err() {
+        if !self.reconnect_state.try_start() {
             return;
         }
+        log::warn!(
+            "[{}] Lost connection to XWayland server. Reconnecting.",
+            self.path
+        );
+        let Ok(iface_ref) = self.get_interface().await else {
+            self.reconnect_state.finish(false);
+            return;
+        };
+        let path = self.path.clone();
         tokio::task::spawn(async move {
-            log::info!("Trying to reconnect to XWayland server.");
-            let interface_ref = &iface_ref.unwrap();
-            let mut iface = interface_ref.get_mut().await;
-            if iface.xwayland.is_connected() {
+            let iface = iface_ref.get().await;
+            if iface.is_connected() {
+                iface.reconnect_state.finish(true);
                 return;
             }
-            if let Err(e) = iface.xwayland.connect() {
-                log::warn!("Failed to reconnect to XWayland server: {:?}", e)
-            }
-            log::info!("Successfully reconnected to XWayland server.");
+            log::debug!("[{}] Reconnecting to XWayland server", path);
+            let success = iface.reconnect_clients();
+            iface.reconnect_state.finish(success);
         });
     }
+
+    /// Records the current time as the last time the given root window atom
+    /// was observed changing
+    fn record_property_change(&self, atom_name: &str) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut times = self.property_change_times.lock().unwrap();
+        times.insert(atom_name.to_string(), timestamp);
+    }
+
+    /// Returns the current output mode, or a zeroed mode if it cannot be read
+    fn output_mode(&self) -> OutputMode {
+        self.x11().get_output_mode().unwrap_or_else(|err| {
+            log::warn!("[{}] Unable to read output mode: {err}", self.path);
+            OutputMode::default()
+        })
+    }
+
+    /// Records a focus of the currently focused app in the app usage stats
+    fn record_app_focus(&self) {
+        let app_id = match self.xwayland().get_focused_app() {
+            Ok(app_id) => app_id.unwrap_or_default(),
+            Err(e) => {
+                log::debug!("[{}] Unable to read focused app: {:?}", self.path, e);
+                return;
+            }
+        };
+        if app_id == 0 {
+            return;
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let mut usage = self.app_usage.lock().unwrap();
+        let entry = usage.entry(app_id).or_default();
+        entry.0 += 1;
+        entry.1 = timestamp;
+    }
+
+    /// Returns the (app_id, focus_count, last_focused_ms) of every app that
+    /// has been focused since the daemon started, most recently focused first
+    pub fn app_usage(&self) -> Vec<(u32, u32, u64)> {
+        let usage = self.app_usage.lock().unwrap();
+        let mut usage: Vec<(u32, u32, u64)> = usage
+            .iter()
+            .map(|(app_id, (count, last_focused))| (*app_id, *count, *last_focused))
+            .collect();
+        usage.sort_by(|a, b| b.2.cmp(&a.2));
+        usage
+    }
+
+    /// Cancels the notification timer for the given window if the window has
+    /// been destroyed
+    pub fn cancel_notification_if_destroyed(&self, window_id: u32) {
+        if !self
+            .notification_timers
+            .lock()
+            .unwrap()
+            .contains_key(&window_id)
+        {
+            return;
+        }
+        match self.x11().window_exists(window_id) {
+            Ok(true) => (),
+            Ok(false) => {
+                let timer = self.notification_timers.lock().unwrap().remove(&window_id);
+                if let Some((_, handle)) = timer {
+                    log::debug!(
+                        "[{}] Cancelling notification for destroyed window {}",
+                        self.path,
+                        window_id
+                    );
+                    handle.abort();
+                }
+            }
+            Err(e) => {
+                log::debug!(
+                    "[{}] Unable to check if window {} exists: {:?}",
+                    self.path,
+                    window_id,
+                    e
+                );
+            }
+        }
+    }
 }
 
 #[dbus_interface(name = "org.shadowblip.Gamescope.XWayland.Primary")]
@@ -418,18 +1543,32 @@ impl DBusInterfacePrimary {
     async fn focusable_apps(&self) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focusable_apps()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
     }
 
+    /// Number of focusable apps. Returns 0 if the list is empty or cannot be
+    /// read.
+    #[dbus_interface(property)]
+    async fn running_app_count(&self) -> u32 {
+        self.ensure_connected().await;
+        match self.xwayland().get_focusable_apps() {
+            Ok(apps) => apps.map(|apps| apps.len() as u32).unwrap_or_default(),
+            Err(e) => {
+                log::debug!("[{}] Unable to read focusable apps: {:?}", self.path, e);
+                0
+            }
+        }
+    }
+
     /// Returns a list of focusable window ids
     #[dbus_interface(property)]
     async fn focusable_windows(&self) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focusable_windows()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -440,7 +1579,7 @@ impl DBusInterfacePrimary {
     async fn focusable_window_names(&self) -> fdo::Result<Vec<String>> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focusable_window_names()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value)
@@ -451,7 +1590,7 @@ impl DBusInterfacePrimary {
     async fn focused_window(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focused_window()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -462,7 +1601,7 @@ impl DBusInterfacePrimary {
     async fn focused_app(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focused_app()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -473,7 +1612,7 @@ impl DBusInterfacePrimary {
     async fn focused_app_gfx(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_focused_app_gfx()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -484,7 +1623,7 @@ impl DBusInterfacePrimary {
     async fn overlay_focused(&self) -> fdo::Result<bool> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .is_overlay_focused()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value)
@@ -495,7 +1634,7 @@ impl DBusInterfacePrimary {
     async fn fps_limit(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_fps_limit()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -503,9 +1642,9 @@ impl DBusInterfacePrimary {
 
     /// Sets the current Gamescope FPS limit
     #[dbus_interface(property)]
-    async fn set_fps_limit(&mut self, fps: u32) -> fdo::Result<()> {
+    async fn set_fps_limit(&self, fps: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_fps_limit(fps)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -516,7 +1655,7 @@ impl DBusInterfacePrimary {
     async fn blur_mode(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_blur_mode()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         if value.is_none() {
@@ -531,7 +1670,7 @@ impl DBusInterfacePrimary {
 
     /// Sets the Gamescope blur mode
     #[dbus_interface(property)]
-    async fn set_blur_mode(&mut self, mode: u32) -> fdo::Result<()> {
+    async fn set_blur_mode(&self, mode: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
         let blur_mode = match mode {
             0 => BlurMode::Off,
@@ -539,52 +1678,345 @@ impl DBusInterfacePrimary {
             2 => BlurMode::Always,
             _ => BlurMode::Off,
         };
-        self.xwayland
+        self.xwayland()
             .set_blur_mode(blur_mode)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
     }
 
-    /// The blur radius size
+    /// Sets the FPS limit, blur mode (0 - off, 1 - cond, 2 - always) and
+    /// whether screen tearing is allowed together, so Gamescope does not
+    /// briefly apply only some of them. Nothing is changed if the blur mode
+    /// is invalid.
+    async fn apply_settings(
+        &self,
+        fps_limit: u32,
+        blur_mode: u32,
+        allow_tearing: bool,
+    ) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if blur_mode > 2 {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Blur mode must be between 0 and 2, got {blur_mode}"
+            )));
+        }
+        let fps_limit_atom = GamescopeAtom::FPSLimit.to_string();
+        let blur_mode_atom = GamescopeAtom::BlurMode.to_string();
+        let allow_tearing_atom = GamescopeAtom::AllowTearing.to_string();
+        let properties: [(&str, &[u32]); 3] = [
+            (&fps_limit_atom, &[fps_limit]),
+            (&blur_mode_atom, &[blur_mode]),
+            (&allow_tearing_atom, &[allow_tearing as u32]),
+        ];
+        let x11 = self.x11();
+        x11.set_cardinal_properties(x11.get_root_window_id(), &properties)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(())
+    }
+
+    /// The blur radius size. Returns 0 if the radius has never been set.
     #[dbus_interface(property)]
     async fn blur_radius(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
-        //let value = self
-        //    .xwayland
-        //    .get_blur_radius()
-        //    .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(0)
+        read_blur_radius(&self.xwayland()).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Sets the blur radius size
+    #[dbus_interface(property)]
+    async fn set_blur_radius(&self, radius: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.xwayland()
+            .set_blur_radius(radius)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Whether or not Gamescope should be allowed to screen tear
+    #[dbus_interface(property)]
+    async fn allow_tearing(&self) -> fdo::Result<bool> {
+        self.ensure_connected().await;
+        read_allow_tearing(&self.xwayland()).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Sets whether or not Gamescope should be allowed to screen tear
+    #[dbus_interface(property)]
+    async fn set_allow_tearing(&self, allow: bool) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.xwayland()
+            .set_allow_tearing(allow)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Sharpness of the upscale filter from 0 (sharpest) to 20 (softest). This
+    /// is the generic sharpness used by both the FSR and NIS filters, and has
+    /// no effect on filters without a sharpness parameter.
+    #[dbus_interface(property)]
+    async fn sharpness(&self) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let x11 = self.x11();
+        let value = x11
+            .get_cardinal_property(x11.get_root_window_id(), SHARPNESS_ATOM)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value
+            .and_then(|values| values.first().copied())
+            .unwrap_or_default())
+    }
+
+    #[dbus_interface(property)]
+    async fn set_sharpness(&self, sharpness: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if sharpness > SHARPNESS_MAX {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Sharpness must be between 0 and {SHARPNESS_MAX}, got {sharpness}"
+            )));
+        }
+        let x11 = self.x11();
+        x11.set_cardinal_property(x11.get_root_window_id(), SHARPNESS_ATOM, &[sharpness])
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Whether or not HDR output is enabled
+    #[dbus_interface(property)]
+    async fn hdr_enabled(&self) -> fdo::Result<bool> {
+        self.ensure_connected().await;
+        read_hdr_enabled(&self.x11()).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[dbus_interface(property)]
+    async fn set_hdr_enabled(&self, enabled: bool) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        write_hdr_enabled(&self.x11(), enabled).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Current output width in pixels, or 0 if unavailable
+    #[dbus_interface(property)]
+    async fn output_width(&self) -> u32 {
+        self.ensure_connected().await;
+        self.output_mode().width
+    }
+
+    /// Current output height in pixels, or 0 if unavailable
+    #[dbus_interface(property)]
+    async fn output_height(&self) -> u32 {
+        self.ensure_connected().await;
+        self.output_mode().height
+    }
+
+    /// Current output refresh rate in Hz, or 0 if unavailable
+    #[dbus_interface(property)]
+    async fn output_refresh(&self) -> u32 {
+        self.ensure_connected().await;
+        self.output_mode().refresh
+    }
+
+    /// The Gamescope upscale filter. The values map to the following filters:
+    /// 0 => linear
+    /// 1 => nearest
+    /// 2 => fsr
+    /// 3 => nis
+    /// 4 => pixel
+    #[dbus_interface(property)]
+    async fn scaling_filter(&self) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        read_scaling_filter(&self.x11()).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[dbus_interface(property)]
+    async fn set_scaling_filter(&self, filter: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if filter as usize >= SCALING_FILTER_NAMES.len() {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Scaling filter must be between 0 and {}, got {filter}",
+                SCALING_FILTER_NAMES.len() - 1
+            )));
+        }
+        write_scaling_filter(&self.x11(), filter).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// How Gamescope scales apps to the output. The values map to the
+    /// following modes:
+    /// 0 => auto
+    /// 1 => integer
+    /// 2 => fit
+    /// 3 => fill
+    /// 4 => stretch
+    #[dbus_interface(property)]
+    async fn scaling_mode(&self) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        read_scaling_mode(&self.x11()).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[dbus_interface(property)]
+    async fn set_scaling_mode(&self, mode: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        if mode as usize >= SCALING_MODE_NAMES.len() {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Scaling mode must be between 0 and {}, got {mode}",
+                SCALING_MODE_NAMES.len() - 1
+            )));
+        }
+        write_scaling_mode(&self.x11(), mode).map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Returns the name of the current Gamescope upscale filter. One of
+    /// "linear", "nearest", "fsr", "nis", or "pixel". Unknown filter values
+    /// are returned as "filter_<value>".
+    async fn get_upscaler_name(&self) -> fdo::Result<String> {
+        self.ensure_connected().await;
+        let value =
+            read_scaling_filter(&self.x11()).map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let name = SCALING_FILTER_NAMES
+            .get(value as usize)
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("filter_{value}"));
+        Ok(name)
+    }
+
+    /// Sets the Gamescope upscale filter by name. Accepts "linear", "nearest",
+    /// "fsr", "nis", or "pixel".
+    async fn set_upscaler_by_name(&self, name: String) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        let Some(value) = SCALING_FILTER_NAMES
+            .iter()
+            .position(|filter| *filter == name.to_lowercase())
+        else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Unknown upscaler name: {name}"
+            )));
+        };
+        write_scaling_filter(&self.x11(), value as u32)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Returns a mapping of app ids to the focusable windows that belong to
+    /// them. Focusable windows without an app id are grouped under app id 0.
+    async fn get_app_window_map(&self) -> fdo::Result<HashMap<u32, Vec<u32>>> {
+        self.ensure_connected().await;
+        let windows = self
+            .xwayland()
+            .get_focusable_windows()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?
+            .unwrap_or_default();
+
+        let mut map: HashMap<u32, Vec<u32>> = HashMap::new();
+        for window_id in windows {
+            let app_id = self
+                .xwayland()
+                .get_app_id(window_id)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?
+                .unwrap_or_default();
+            let app_windows = map.entry(app_id).or_default();
+            if !app_windows.contains(&window_id) {
+                app_windows.push(window_id);
+            }
+        }
+
+        Ok(map)
     }
 
-    /// Sets the blur radius size
-    #[dbus_interface(property)]
-    async fn set_blur_radius(&mut self, radius: u32) -> fdo::Result<()> {
+    /// Recursively returns the windows beneath the given root window that match
+    /// all of the given filters:
+    /// - `app_id_filter`: only windows with this app ID. 0 matches any window.
+    /// - `overlay_only`: only windows with the STEAM_OVERLAY property set.
+    /// - `mapped_only`: only windows that are mapped and viewable.
+    /// Windows that are destroyed while the query runs are skipped.
+    async fn query_windows(
+        &self,
+        root_window_id: u32,
+        app_id_filter: u32,
+        overlay_only: bool,
+        mapped_only: bool,
+    ) -> fdo::Result<Vec<u32>> {
         self.ensure_connected().await;
-        self.xwayland
-            .set_blur_radius(radius)
+        let windows = self
+            .xwayland()
+            .get_all_windows(root_window_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(())
+
+        let mut matches = Vec::new();
+        for window_id in windows {
+            if app_id_filter != 0 {
+                let app_id = self.xwayland().get_app_id(window_id).ok().flatten();
+                if app_id != Some(app_id_filter) {
+                    continue;
+                }
+            }
+            if overlay_only {
+                let overlay = self.xwayland().get_overlay(window_id).ok().flatten();
+                if overlay.unwrap_or_default() == 0 {
+                    continue;
+                }
+            }
+            if mapped_only && !self.x11().is_window_viewable(window_id).unwrap_or(false) {
+                continue;
+            }
+            matches.push(window_id);
+        }
+
+        Ok(matches)
     }
 
-    /// Whether or not Gamescope should be allowed to screen tear
-    #[dbus_interface(property)]
-    async fn allow_tearing(&self) -> fdo::Result<bool> {
+    /// Returns a snapshot of the gamescope atoms currently set on the root
+    /// window, mapping each atom name to its CARDINAL values. Atoms that are
+    /// not set are omitted.
+    async fn dump_root_state(&self) -> fdo::Result<HashMap<String, Vec<u32>>> {
         self.ensure_connected().await;
-        //let value = self
-        //    .xwayland
-        //    .get_allow_tearing()
-        //    .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(false)
+        let root_window_id = self
+            .xwayland()
+            .get_root_window_id()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        let mut state = HashMap::new();
+        for atom in ROOT_STATE_ATOMS {
+            let value = self
+                .xwayland()
+                .get_xprop(root_window_id, atom)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if let Some(value) = value {
+                state.insert(atom.to_string(), value);
+            }
+        }
+        for name in [
+            SCALING_FILTER_ATOM,
+            SCALING_MODE_ATOM,
+            SHARPNESS_ATOM,
+            HDR_ENABLED_ATOM,
+        ] {
+            let value = self
+                .x11()
+                .get_cardinal_property(root_window_id, name)
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+            if let Some(value) = value {
+                state.insert(name.to_string(), value);
+            }
+        }
+
+        Ok(state)
     }
 
-    /// Sets whether or not Gamescope should be allowed to screen tear
-    #[dbus_interface(property)]
-    async fn set_allow_tearing(&mut self, allow: bool) -> fdo::Result<()> {
+    /// Returns the current CARDINAL value(s) of the given root window atom
+    /// along with the unix time in milliseconds when the daemon last observed
+    /// the atom changing. The timestamp is 0 if no change has been observed
+    /// since the daemon started.
+    async fn get_root_property_with_timestamp(
+        &self,
+        atom_name: String,
+    ) -> fdo::Result<(Vec<u32>, u64)> {
         self.ensure_connected().await;
-        self.xwayland
-            .set_allow_tearing(allow)
+        let x11 = self.x11();
+        let value = x11
+            .get_cardinal_property(x11.get_root_window_id(), &atom_name)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
-        Ok(())
+        let timestamp = self
+            .property_change_times
+            .lock()
+            .unwrap()
+            .get(&atom_name)
+            .copied()
+            .unwrap_or_default();
+        Ok((value.unwrap_or_default(), timestamp))
     }
 
     /// Returns true if the window with the given window ID exists in focusable apps
@@ -592,12 +2024,111 @@ impl DBusInterfacePrimary {
     async fn is_focusable_app(&self, window_id: u32) -> fdo::Result<bool> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .is_focusable_app(window_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value)
     }
 
+    /// Returns true if the given app id is in the list of focusable apps
+    #[dbus_interface(out_args("is_focusable"))]
+    async fn is_app_focusable(&self, app_id: u32) -> fdo::Result<bool> {
+        self.ensure_connected().await;
+        let value = self
+            .xwayland()
+            .get_focusable_apps()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value.unwrap_or_default().contains(&app_id))
+    }
+
+    /// Waits for the given app id to be in the list of focusable apps.
+    /// Returns true once it is, or false if it does not become focusable
+    /// within the given timeout. Timeouts are capped at 60 seconds.
+    async fn wait_for_focusable_app(&self, app_id: u32, timeout_ms: u32) -> fdo::Result<bool> {
+        // Subscribe before checking so a change in between is not missed
+        let mut changes = self.focusable_apps_changes.subscribe();
+        let timeout = Duration::from_millis(timeout_ms as u64).min(FOCUSABLE_APP_MAX_WAIT);
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if self.is_app_focusable(app_id).await? {
+                return Ok(true);
+            }
+            match tokio::time::timeout_at(deadline, changes.changed()).await {
+                Ok(Ok(())) => continue,
+                Ok(Err(_)) | Err(_) => return Ok(false),
+            }
+        }
+    }
+
+    /// Returns the current focus context, which can be used to decide where
+    /// input should be routed:
+    /// 0 - a game is focused (the overlay is not focused)
+    /// 1 - the overlay is focused but is not intercepting input
+    /// 2 - the overlay is focused and is intercepting input
+    /// The overlay is considered focused if gamescope reports it as focused or
+    /// if the focused window has the overlay property set. It is considered to
+    /// be intercepting input if the focused window has STEAM_INPUT_FOCUS set
+    /// to a non-zero value.
+    #[dbus_interface(out_args("context"))]
+    async fn get_focus_context(&self) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let window_id = self
+            .xwayland()
+            .get_focused_window()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?
+            .unwrap_or_default();
+        // Nothing is focused, so there is no window to read properties from
+        if window_id == 0 {
+            return Ok(0);
+        }
+        let overlay_focused = self
+            .xwayland()
+            .is_overlay_focused()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let overlay = self
+            .xwayland()
+            .get_overlay(window_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?
+            .unwrap_or_default();
+        if !overlay_focused && overlay == 0 {
+            return Ok(0);
+        }
+
+        let input_focus = self
+            .xwayland()
+            .get_xprop(window_id, GamescopeAtom::SteamInputFocus)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?
+            .and_then(|values| values.first().copied())
+            .unwrap_or_default();
+        if input_focus == 0 {
+            return Ok(1);
+        }
+
+        Ok(2)
+    }
+
+    /// Forces a new connection to the XWayland server, even if the current
+    /// connection appears valid. Returns whether or not reconnecting
+    /// succeeded. Concurrent calls are serialized, so only one reconnect is
+    /// attempted at a time.
+    async fn reconnect(&self) -> fdo::Result<bool> {
+        let success = self.reconnect_clients();
+        if success {
+            self.reconnect_state.finish(true);
+        }
+        Ok(success)
+    }
+
+    /// Fires when the focused app or window changes, carrying the newly
+    /// focused app id and window id. Rapid successive changes are debounced,
+    /// so only the final focus is signaled.
+    #[dbus_interface(signal)]
+    async fn app_focus_changed(
+        ctxt: &SignalContext<'_>,
+        app_id: u32,
+        window_id: u32,
+    ) -> zbus::Result<()>;
+
     /// Fires when the baselayer app id has been updated
     #[dbus_interface(signal)]
     async fn baselayer_app_id_updated(ctxt: &SignalContext<'_>) -> zbus::Result<()>;
@@ -611,12 +2142,35 @@ impl DBusInterfacePrimary {
     #[deprecated(since = "1.5.0", note = "please use `window_lifecycle` instead")]
     async fn window_created(ctxt: &SignalContext<'_>, window_id: u32) -> zbus::Result<()>;
 
+    /// Re-emits the change signals for all signaled properties with their
+    /// current values. Clients that subscribe after startup can call this to
+    /// receive the current state instead of reading each property.
+    async fn refresh_signals(
+        &self,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.focused_app_changed(&ctxt).await?;
+        self.focusable_apps_changed(&ctxt).await?;
+        self.focused_app_gfx_changed(&ctxt).await?;
+        self.focused_window_changed(&ctxt).await?;
+        self.focusable_windows_changed(&ctxt).await?;
+        self.running_app_count_changed(&ctxt).await?;
+        self.sharpness_changed(&ctxt).await?;
+        self.scaling_filter_changed(&ctxt).await?;
+        self.scaling_mode_changed(&ctxt).await?;
+        self.hdr_enabled_changed(&ctxt).await?;
+        Self::baselayer_window_updated(&ctxt).await?;
+        Self::baselayer_app_id_updated(&ctxt).await?;
+        Ok(())
+    }
+
     /// Sets the given window as the main launcher app. This will set an X window
     /// property called STEAM_GAME to 769 (Steam), which will make Gamescope
     /// treat the window as the main overlay.
     async fn set_main_app(&self, window_id: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_main_app(window_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -626,26 +2180,42 @@ impl DBusInterfacePrimary {
     /// "1" whenever the overlay wants to intercept input from a game.
     async fn set_input_focus(&self, window_id: u32, value: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_input_focus(window_id, value)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
     }
 
-    /// Get the overlay status for the given window
+    /// Returns the input focus value set on the given window with
+    /// [SetInputFocus], or 0 if it is not set
+    async fn get_input_focus(&self, window_id: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let value = self
+            .xwayland()
+            .get_xprop(window_id, GamescopeAtom::SteamInputFocus)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value
+            .and_then(|values| values.first().copied())
+            .unwrap_or_default())
+    }
+
+    /// Returns the STEAM_OVERLAY value of the given window. 1 means the window
+    /// is treated as an overlay and 0 or unset means it is not.
     async fn get_overlay(&self, window_id: u32) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_overlay(window_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
     }
 
-    /// Set the given window as the main overlay window
+    /// Sets the STEAM_OVERLAY value of the given window, which marks it as
+    /// the main overlay window. Use 1 to mark the window as an overlay and 0
+    /// to unmark it.
     async fn set_overlay(&self, window_id: u32, value: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_overlay(window_id, value)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -655,26 +2225,148 @@ impl DBusInterfacePrimary {
     /// UI wants to be shown but not intercept input.
     async fn set_notification(&self, window_id: u32, value: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_notification(window_id, value)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
     }
 
+    /// Returns the notification value set on the given window with
+    /// [SetNotification], or 0 if it is not set
+    async fn get_notification(&self, window_id: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let value = self
+            .xwayland()
+            .get_xprop(window_id, GamescopeAtom::SteamNotification)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value
+            .and_then(|values| values.first().copied())
+            .unwrap_or_default())
+    }
+
+    /// Shows the given window as a notification for the given duration in
+    /// milliseconds, after which the notification is automatically cleared.
+    /// Calling this again for the same window before the duration elapses
+    /// resets the timer.
+    async fn show_notification(&self, window_id: u32, duration_ms: u32) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.xwayland()
+            .set_notification(window_id, 1)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        // Spawn a task to clear the notification after the given duration.
+        // The timers are kept locked until the new handle is inserted so the
+        // task cannot check its generation before it is recorded.
+        let generation = self.notification_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut timers = self.notification_timers.lock().unwrap();
+        let conn = self.dbus.clone();
+        let path = self.path.clone();
+        let handle = tokio::task::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(duration_ms as u64)).await;
+            let Ok(iface_ref) = conn
+                .object_server()
+                .interface::<_, DBusInterfacePrimary>(path.clone())
+                .await
+            else {
+                log::warn!(
+                    "[{}] Not able to find dbus interface to clear notification",
+                    path
+                );
+                return;
+            };
+            let iface = iface_ref.get().await;
+
+            // Only clear the notification if this timer was not superseded by
+            // a newer notification or cancelled in the meantime
+            {
+                let mut timers = iface.notification_timers.lock().unwrap();
+                match timers.get(&window_id) {
+                    Some((current, _)) if *current == generation => {
+                        timers.remove(&window_id);
+                    }
+                    _ => return,
+                }
+            }
+
+            // If the window was destroyed in the meantime, its notification
+            // property no longer exists and there is nothing to clear.
+            if let Err(e) = iface.xwayland().set_notification(window_id, 0) {
+                log::debug!(
+                    "[{}] Unable to clear notification on window {}: {:?}",
+                    path,
+                    window_id,
+                    e
+                );
+            }
+        })
+        .abort_handle();
+
+        // Reset any existing timer for the window
+        if let Some((_, previous)) = timers.insert(window_id, (generation, handle)) {
+            previous.abort();
+        }
+
+        Ok(())
+    }
+
     /// Set the given window as an external overlay window
     async fn set_external_overlay(&self, window_id: u32, value: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_external_overlay(window_id, value)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
     }
 
+    /// Returns the external overlay value set on the given window with
+    /// [SetExternalOverlay], or 0 if it is not set
+    async fn get_external_overlay(&self, window_id: u32) -> fdo::Result<u32> {
+        self.ensure_connected().await;
+        let value = self
+            .xwayland()
+            .get_xprop(window_id, GamescopeAtom::ExternalOverlay)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value
+            .and_then(|values| values.first().copied())
+            .unwrap_or_default())
+    }
+
+    /// Returns the CARDINAL values of the root window property with the given
+    /// atom name (E.g. "GAMESCOPECTRL_BASELAYER_APPID"), or an empty list if
+    /// the property is not set. In strict mode, an error is returned if the
+    /// atom is unknown to the X server.
+    async fn get_root_atom(&self, name: String) -> fdo::Result<Vec<u32>> {
+        self.ensure_connected().await;
+        self.validate_root_atom(&name)?;
+        let x11 = self.x11();
+        let value = x11
+            .get_cardinal_property(x11.get_root_window_id(), &name)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(value.unwrap_or_default())
+    }
+
+    /// Sets the root window property with the given atom name to the given
+    /// CARDINAL values. In strict mode, an error is returned if the atom is
+    /// unknown to the X server instead of interning it.
+    async fn set_root_atom(&self, name: String, values: Vec<u32>) -> fdo::Result<()> {
+        self.ensure_connected().await;
+        self.validate_root_atom(&name)?;
+        let x11 = self.x11();
+        x11.set_cardinal_property(x11.get_root_window_id(), &name, &values)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    /// Enables or disables strict mode for [GetRootAtom] and [SetRootAtom].
+    /// Strict mode is enabled by default.
+    async fn set_strict_atoms(&self, enabled: bool) {
+        self.strict_atoms.store(enabled, Ordering::SeqCst);
+    }
+
     /// Returns the currently set manual app focus
     async fn get_baselayer_app_id(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_baselayer_app_id()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -683,7 +2375,32 @@ impl DBusInterfacePrimary {
     /// Focuses the app with the given app id
     async fn set_baselayer_app_id(&self, app_id: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
+            .set_baselayer_app_id(app_id)
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        Ok(())
+    }
+
+    /// Focuses the app with the given app id on the gamescope XWayland
+    /// instance with the given display number (E.g. 1 for ":1"). Returns an
+    /// error if no such instance exists.
+    async fn set_baselayer_app_id_on(&self, xwayland_id: u32, app_id: u32) -> fdo::Result<()> {
+        let displays = gamescope_x11_client::discover_gamescope_displays()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let Some(name) = displays
+            .into_iter()
+            .find(|name| parse_display_number(name) == Some(xwayland_id))
+        else {
+            return Err(fdo::Error::Failed(format!(
+                "No gamescope XWayland found with display number {xwayland_id}"
+            )));
+        };
+
+        let mut xwayland = XWayland::new(name);
+        xwayland
+            .connect()
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        xwayland
             .set_baselayer_app_id(app_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -692,7 +2409,7 @@ impl DBusInterfacePrimary {
     /// Removes the baselayer property to un-focus an app
     async fn remove_baselayer_app_id(&self) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .remove_baselayer_app_id()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -702,7 +2419,7 @@ impl DBusInterfacePrimary {
     async fn get_baselayer_window(&self) -> fdo::Result<u32> {
         self.ensure_connected().await;
         let value = self
-            .xwayland
+            .xwayland()
             .get_baselayer_window()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(value.unwrap_or_default())
@@ -711,7 +2428,7 @@ impl DBusInterfacePrimary {
     /// Focuses the given window
     async fn set_baselayer_window(&self, window_id: u32) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .set_baselayer_window(window_id)
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -720,7 +2437,7 @@ impl DBusInterfacePrimary {
     /// Removes the baselayer property to un-focus windows
     async fn remove_baselayer_window(&self) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .remove_baselayer_window()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
@@ -729,11 +2446,63 @@ impl DBusInterfacePrimary {
     /// Request a screenshot from Gamescope
     async fn request_screenshot(&self) -> fdo::Result<()> {
         self.ensure_connected().await;
-        self.xwayland
+        self.xwayland()
             .request_screenshot()
             .map_err(|err| fdo::Error::Failed(err.to_string()))?;
         Ok(())
     }
+
+    /// Requests a screenshot from Gamescope and waits up to the given timeout
+    /// for it to be saved. Returns the path of the saved screenshot. Requires
+    /// the Gamescope Wayland instance serving this display to receive the
+    /// completion notification. Timeouts are capped at 30 seconds.
+    async fn request_screenshot_blocking(&self, timeout_ms: u32) -> fdo::Result<String> {
+        self.ensure_connected().await;
+        let (sender, mut receiver) = mpsc::channel(1);
+        let socket_name = get_wayland_display(&self.xwayland().get_name());
+        self.manager_tx
+            .send(Command::WaylandManager {
+                socket_name,
+                sender,
+            })
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        let Some(wayland) = receiver.recv().await.flatten() else {
+            return Err(fdo::Error::Failed(
+                "No Gamescope Wayland instance available to wait for the screenshot".into(),
+            ));
+        };
+
+        let (armed_tx, mut armed_rx) = mpsc::channel(1);
+        let (tx, mut rx) = mpsc::channel(1);
+        let timeout = Duration::from_millis(timeout_ms as u64).min(SCREENSHOT_MAX_WAIT);
+        wayland
+            .send(WaylandMessage::CommandWaitForScreenshot(
+                armed_tx, tx, timeout,
+            ))
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+
+        // Only request the screenshot once the notification can't be missed
+        if armed_rx.recv().await.is_some() {
+            self.xwayland()
+                .request_screenshot()
+                .map_err(|err| fdo::Error::Failed(err.to_string()))?;
+        }
+
+        match rx.recv().await {
+            Some(Ok(path)) => Ok(path),
+            Some(Err(ScreenshotError::TimedOut)) => Err(fdo::Error::TimedOut(format!(
+                "No screenshot was reported within {}ms",
+                timeout.as_millis()
+            ))),
+            Some(Err(ScreenshotError::Unsupported(err))) => Err(fdo::Error::NotSupported(err)),
+            Some(Err(ScreenshotError::Failed(err))) => Err(fdo::Error::Failed(err)),
+            None => Err(fdo::Error::Failed(
+                "No response received for screenshot".into(),
+            )),
+        }
+    }
 }
 
 /// Listen for property changes and emit the appropriate DBus signals. This is
@@ -743,16 +2512,20 @@ pub async fn dispatch_primary_property_changes(
     conn: zbus::Connection,
     path: String,
     rx: Receiver<String>,
+    focusable_apps_changes: Arc<watch::Sender<()>>,
 ) -> Result<(), Box<dyn Error>> {
     tokio::task::spawn_blocking(move || {
-        log::debug!("Started listening for property changes");
+        log::debug!("[{}] Started listening for property changes", path);
 
         // Wait for events from the channel and dispatch them to the DBus interface
         while let Ok(event) = rx.recv() {
             // log::trace!("Got property change event: {:?}", event);
+            if event == GamescopeAtom::FocusableApps.to_string() {
+                focusable_apps_changes.send_replace(());
+            }
             dispatch_property_change_to_dbus(conn.clone(), path.clone(), event);
         }
-        log::warn!("Stopped listening for property changes");
+        log::warn!("[{}] Stopped listening for property changes", path);
     });
 
     Ok(())
@@ -771,15 +2544,15 @@ pub async fn dispatch_primary_window_created(
     rx: Receiver<u32>,
 ) -> Result<(), Box<dyn Error>> {
     tokio::task::spawn_blocking(move || {
-        log::debug!("Started listening for windows created");
+        log::debug!("[{}] Started listening for windows created", path);
 
         // Wait for events from the channel and dispatch them to the DBus interface
         while let Ok(event) = rx.recv() {
-            log::debug!("Got window created event: {:?}", event);
+            log::debug!("[{}] Got window created event: {:?}", path, event);
             #[allow(deprecated)]
             dispatch_window_created_to_dbus(conn.clone(), path.clone(), event);
         }
-        log::warn!("Stopped listening for windows created");
+        log::warn!("[{}] Stopped listening for windows created", path);
     });
 
     Ok(())
@@ -795,12 +2568,13 @@ pub async fn dispatch_window_lifecycle(
     is_primary: bool,
 ) -> Result<(), Box<dyn Error>> {
     tokio::task::spawn_blocking(move || {
-        log::debug!("Started listening for windows lifecycle");
+        log::debug!("[{}] Started listening for windows lifecycle", path);
 
         // Wait for events from the channel and dispatch them to the DBus interface
         while let Ok((lifecycle_event, window_id)) = rx.recv() {
             log::debug!(
-                "Got window lifecycle event: {:?} for window id: {:?}",
+                "[{}] Got window lifecycle event: {:?} for window id: {:?}",
+                path,
                 lifecycle_event,
                 window_id
             );
@@ -812,12 +2586,165 @@ pub async fn dispatch_window_lifecycle(
                 is_primary,
             );
         }
-        log::warn!("Stopped listening for windows lifecycle");
+        log::warn!("[{}] Stopped listening for windows lifecycle", path);
     });
 
     Ok(())
 }
 
+/// Returns the display number of the given X display name. Accepts names with
+/// an optional host and screen (E.g. ":1", "localhost:1.0").
+fn parse_display_number(name: &str) -> Option<u32> {
+    let (_, display) = name.rsplit_once(':')?;
+    let number = display.split('.').next()?;
+    number.parse().ok()
+}
+
+/// Returns whether the given X display is served by a primary Gamescope
+/// XWayland instance
+pub fn is_primary_display(name: &str) -> Result<bool, Box<dyn Error>> {
+    let mut xwayland = XWayland::new(name.to_string());
+    xwayland.connect()?;
+    xwayland.is_primary_instance()
+}
+
+/// Returns the name of the Gamescope Wayland socket (E.g. "gamescope-0") of
+/// the Gamescope instance serving the given X display. This is read from the
+/// environment Gamescope passes to the Xwayland server listening on the
+/// display, and is None if it cannot be determined.
+pub fn get_wayland_display(name: &str) -> Option<String> {
+    let number = parse_display_number(name)?;
+    let stream = UnixStream::connect(format!("/tmp/.X11-unix/X{number}")).ok()?;
+    let credentials = getsockopt(&stream, PeerCredentials).ok()?;
+    let environ = fs::read(format!("/proc/{}/environ", credentials.pid())).ok()?;
+    environ
+        .split(|byte| *byte == 0)
+        .find_map(|var| var.strip_prefix(b"GAMESCOPE_WAYLAND_DISPLAY="))
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+}
+
+/// Returns the first non-zero app id found on the given windows, checking
+/// each window and then its top-level window, or 0 if none have one
+fn find_app_id<T, A, E>(windows: &[u32], toplevel: T, mut app_id: A) -> Result<u32, E>
+where
+    T: Fn(u32) -> Option<u32>,
+    A: FnMut(u32) -> Result<Option<u32>, E>,
+{
+    for window_id in windows.iter().copied() {
+        for window_id in std::iter::once(window_id).chain(toplevel(window_id)) {
+            let value = app_id(window_id)?.unwrap_or_default();
+            if value != 0 {
+                return Ok(value);
+            }
+        }
+    }
+    Ok(0)
+}
+
+/// Returns the blur radius set on the root window of the given XWayland
+/// instance, or 0 if it has never been set
+fn read_blur_radius(xwayland: &XWayland) -> Result<u32, Box<dyn Error>> {
+    let root_window_id = xwayland.get_root_window_id()?;
+    let value = xwayland.get_xprop(root_window_id, GamescopeAtom::BlurRadius)?;
+    Ok(value
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default())
+}
+
+/// Returns whether tearing is allowed on the given XWayland instance
+fn read_allow_tearing(xwayland: &XWayland) -> Result<bool, Box<dyn Error>> {
+    let root_window_id = xwayland.get_root_window_id()?;
+    let value = xwayland.get_xprop(root_window_id, GamescopeAtom::AllowTearing)?;
+    Ok(value
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default()
+        != 0)
+}
+
+/// Returns the upscale filter set on the given X display, or 0 (linear) if
+/// none is set
+fn read_scaling_filter(x11: &X11Client) -> Result<u32, Box<dyn Error>> {
+    let value = x11.get_cardinal_property(x11.get_root_window_id(), SCALING_FILTER_ATOM)?;
+    Ok(value
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default())
+}
+
+/// Sets the upscale filter on the given X display
+fn write_scaling_filter(x11: &X11Client, filter: u32) -> Result<(), Box<dyn Error>> {
+    x11.set_cardinal_property(x11.get_root_window_id(), SCALING_FILTER_ATOM, &[filter])
+}
+
+/// Returns the scaling mode set on the given X display, or 0 (auto) if none
+/// is set
+fn read_scaling_mode(x11: &X11Client) -> Result<u32, Box<dyn Error>> {
+    let value = x11.get_cardinal_property(x11.get_root_window_id(), SCALING_MODE_ATOM)?;
+    Ok(value
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default())
+}
+
+/// Sets the scaling mode on the given X display
+fn write_scaling_mode(x11: &X11Client, mode: u32) -> Result<(), Box<dyn Error>> {
+    x11.set_cardinal_property(x11.get_root_window_id(), SCALING_MODE_ATOM, &[mode])
+}
+
+/// Returns whether HDR output is enabled on the given X display
+fn read_hdr_enabled(x11: &X11Client) -> Result<bool, Box<dyn Error>> {
+    let value = x11.get_cardinal_property(x11.get_root_window_id(), HDR_ENABLED_ATOM)?;
+    Ok(value
+        .and_then(|values| values.first().copied())
+        .unwrap_or_default()
+        != 0)
+}
+
+/// Enables or disables HDR output on the given X display
+fn write_hdr_enabled(x11: &X11Client, enabled: bool) -> Result<(), Box<dyn Error>> {
+    x11.set_cardinal_property(
+        x11.get_root_window_id(),
+        HDR_ENABLED_ATOM,
+        &[enabled as u32],
+    )
+}
+
+/// Reconnects the given XWayland and X11 connections. Callers must hold
+/// exclusive access to the interface so reconnects cannot race. Returns
+/// whether or not both connections were re-established.
+fn reconnect(path: &str, xwayland: &mut XWayland, x11: &mut X11Client) -> bool {
+    log::info!("[{}] Forcing reconnect to XWayland server.", path);
+    if let Err(e) = xwayland.connect() {
+        log::warn!("[{}] Failed to reconnect to XWayland server: {:?}", path, e);
+        return false;
+    }
+    match X11Client::connect(&xwayland.get_name()) {
+        Ok(client) => *x11 = client,
+        Err(e) => {
+            log::warn!("[{}] Failed to reconnect to X11 server: {:?}", path, e);
+            return false;
+        }
+    }
+    log::info!("[{}] Successfully reconnected to XWayland server.", path);
+    true
+}
+
+/// Returns the cached lookup result for the given key if it is fresh,
+/// otherwise computes and caches a new result.
+fn cached_lookup<F, E>(cache: &LookupCache, key: u32, lookup: F) -> Result<Vec<u32>, E>
+where
+    F: FnOnce() -> Result<Vec<u32>, E>,
+{
+    if let Some((time, value)) = cache.lock().unwrap().get(&key) {
+        if time.elapsed() < WINDOW_CACHE_TTL {
+            return Ok(value.clone());
+        }
+    }
+    let value = lookup()?;
+    let mut cache = cache.lock().unwrap();
+    cache.retain(|_, (time, _)| time.elapsed() < WINDOW_CACHE_TTL);
+    cache.insert(key, (Instant::now(), value.clone()));
+    Ok(value)
+}
+
 /// Dispatch the given event to DBus using async
 fn dispatch_property_change_to_dbus(conn: zbus::Connection, path: String, event: String) {
     tokio::task::spawn(async move {
@@ -825,57 +2752,148 @@ fn dispatch_property_change_to_dbus(conn: zbus::Connection, path: String, event:
         // updates
         let Ok(iface_ref) = conn
             .object_server()
-            .interface::<_, DBusInterfacePrimary>(path)
+            .interface::<_, DBusInterfacePrimary>(path.clone())
             .await
         else {
-            log::warn!("Not able to find dbus interface to dispatch property change event");
+            log::warn!(
+                "[{}] Not able to find dbus interface to dispatch property change event",
+                path
+            );
             return;
         };
 
-        let iface = iface_ref.get_mut().await;
+        let iface = iface_ref.get().await;
         // log::trace!("Got property change event: {:?}", event);
+        iface.record_property_change(&event);
 
         // Match on the type of property that was changed to send the appropriate
         // DBus signal.
         // NOTE: These should only be defined for "read-only" properties
         // TODO: Maybe this can be automatically expressed better using a macro
+        if event == GamescopeAtom::FocusedApp.to_string()
+            || event == GamescopeAtom::FocusedWindow.to_string()
+        {
+            let generation = iface.app_focus_generation.fetch_add(1, Ordering::SeqCst) + 1;
+            dispatch_app_focus_changed_to_dbus(conn.clone(), path.clone(), generation);
+        }
+
         if event == GamescopeAtom::FocusedApp.to_string() {
+            iface.record_app_focus();
             iface
                 .focused_app_changed(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::FocusableApps.to_string() {
             iface
                 .focusable_apps_changed(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+            iface
+                .running_app_count_changed(iface_ref.signal_context())
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::FocusedAppGFX.to_string() {
             iface
                 .focused_app_gfx_changed(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::FocusedWindow.to_string() {
             iface
                 .focused_window_changed(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::FocusableWindows.to_string() {
             iface
                 .focusable_windows_changed(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+        } else if event == SHARPNESS_ATOM {
+            iface
+                .sharpness_changed(iface_ref.signal_context())
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+        } else if event == SCALING_FILTER_ATOM {
+            iface
+                .scaling_filter_changed(iface_ref.signal_context())
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+        } else if event == SCALING_MODE_ATOM {
+            iface
+                .scaling_mode_changed(iface_ref.signal_context())
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
+        } else if event == HDR_ENABLED_ATOM {
+            iface
+                .hdr_enabled_changed(iface_ref.signal_context())
+                .await
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::BaselayerWindow.to_string() {
             DBusInterfacePrimary::baselayer_window_updated(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         } else if event == GamescopeAtom::BaselayerAppId.to_string() {
             DBusInterfacePrimary::baselayer_app_id_updated(iface_ref.signal_context())
                 .await
-                .unwrap_or_else(|error| log::warn!("Unable to signal value change: {:?}", error));
+                .unwrap_or_else(|error| {
+                    log::warn!("[{}] Unable to signal value change: {:?}", path, error)
+                });
         }
     });
 }
 
+/// Emits [AppFocusChanged] after [APP_FOCUS_DEBOUNCE] if no other focus
+/// change has happened since the one with the given generation
+fn dispatch_app_focus_changed_to_dbus(conn: zbus::Connection, path: String, generation: u64) {
+    tokio::task::spawn(async move {
+        tokio::time::sleep(APP_FOCUS_DEBOUNCE).await;
+        let Ok(iface_ref) = conn
+            .object_server()
+            .interface::<_, DBusInterfacePrimary>(path.clone())
+            .await
+        else {
+            return;
+        };
+
+        let iface = iface_ref.get().await;
+        if iface.app_focus_generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        let app_id = iface.xwayland().get_focused_app().unwrap_or_default();
+        let window_id = iface.xwayland().get_focused_window().unwrap_or_default();
+        DBusInterfacePrimary::app_focus_changed(
+            iface_ref.signal_context(),
+            app_id.unwrap_or_default(),
+            window_id.unwrap_or_default(),
+        )
+        .await
+        .unwrap_or_else(|error| {
+            log::warn!("[{}] Unable to signal app focus change: {:?}", path, error)
+        });
+    });
+}
+
 /// Dispatch the given event to DBus using async
 #[deprecated(
     since = "1.5.0",
@@ -887,17 +2905,21 @@ fn dispatch_window_created_to_dbus(conn: zbus::Connection, path: String, value:
         // updates
         let iface_ref = conn
             .object_server()
-            .interface::<_, DBusInterfacePrimary>(path)
+            .interface::<_, DBusInterfacePrimary>(path.clone())
             .await
             .expect("Unable to get reference to DBus interface");
 
-        log::debug!("Got window created for window_id: {:?}", value);
+        log::debug!("[{}] Got window created for window_id: {:?}", path, value);
 
         #[allow(deprecated)]
         DBusInterfacePrimary::window_created(iface_ref.signal_context(), value)
             .await
             .unwrap_or_else(|error| {
-                log::warn!("Unable to signal window created event: {:?}", error);
+                log::warn!(
+                    "[{}] Unable to signal window created event: {:?}",
+                    path,
+                    error
+                );
             });
     });
 }
@@ -915,19 +2937,44 @@ fn dispatch_window_lifecycle_to_dbus(
         // updates
         let Ok(iface_ref) = conn
             .object_server()
-            .interface::<_, DBusInterface>(path)
+            .interface::<_, DBusInterface>(path.clone())
             .await
         else {
-            log::warn!("Not able to find dbus interface to dispatch window lifecycle event");
+            log::warn!(
+                "[{}] Not able to find dbus interface to dispatch window lifecycle event",
+                path
+            );
             return;
         };
 
         log::debug!(
-            "Got window lifecycle event: {:?} for window_id: {:?}",
+            "[{}] Got window lifecycle event: {:?} for window_id: {:?}",
+            path,
             lifecycle_event,
             window_id
         );
 
+        // Cached lookups may reference windows that changed
+        let diagnostics = {
+            let iface = iface_ref.get().await;
+            iface.invalidate_window_cache();
+            iface.diagnostics.clone()
+        };
+
+        // Notifications shown on a destroyed window no longer need clearing
+        if is_primary {
+            if let Ok(primary_ref) = conn
+                .object_server()
+                .interface::<_, DBusInterfacePrimary>(path.clone())
+                .await
+            {
+                primary_ref
+                    .get()
+                    .await
+                    .cancel_notification_if_destroyed(window_id);
+            }
+        }
+
         DBusInterface::window_lifecycle(
             iface_ref.signal_context(),
             lifecycle_event.to_string(),
@@ -935,8 +2982,210 @@ fn dispatch_window_lifecycle_to_dbus(
             is_primary,
         )
         .await
+        .map(|_| InterfaceDiagnostics::increment(&diagnostics.signals_dispatched))
         .unwrap_or_else(|error| {
-            log::warn!("Unable to signal window lifecycle event: {:?}", error);
+            log::warn!(
+                "[{}] Unable to signal window lifecycle event: {:?}",
+                path,
+                error
+            );
         });
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Connects to the X display named in GAMESCOPE_DBUS_TEST_DISPLAY (E.g.
+    /// ":1"). Tests using it change root window properties, so they are
+    /// ignored by default and should only be run against a nested gamescope
+    /// or a virtual X server.
+    fn test_xwayland() -> XWayland {
+        let name = env::var("GAMESCOPE_DBUS_TEST_DISPLAY")
+            .expect("GAMESCOPE_DBUS_TEST_DISPLAY must name an X display");
+        let mut xwayland = XWayland::new(name);
+        xwayland
+            .connect()
+            .expect("Unable to connect to the test display");
+        xwayland
+    }
+
+    /// Connects an [X11Client] to the display used by [test_xwayland]
+    fn test_x11() -> X11Client {
+        let name = env::var("GAMESCOPE_DBUS_TEST_DISPLAY")
+            .expect("GAMESCOPE_DBUS_TEST_DISPLAY must name an X display");
+        X11Client::connect(&name).expect("Unable to connect to the test display")
+    }
+
+    /// Looks up the app id for the given windows in a fake window tree, where
+    /// each window maps to its top-level window and app id
+    fn find_app_id_in(tree: &HashMap<u32, (u32, Option<u32>)>, windows: &[u32]) -> u32 {
+        find_app_id::<_, _, ()>(
+            windows,
+            |window_id| tree.get(&window_id).map(|(toplevel, _)| *toplevel),
+            |window_id| Ok(tree.get(&window_id).and_then(|(_, app_id)| *app_id)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn app_id_is_read_from_the_window_that_has_it() {
+        // Only the second window of the process carries STEAM_GAME
+        let tree = HashMap::from([(10, (10, None)), (11, (11, Some(7))), (12, (12, Some(0)))]);
+        assert_eq!(find_app_id_in(&tree, &[10, 11, 12]), 7);
+    }
+
+    #[test]
+    fn app_id_falls_back_to_the_toplevel_window() {
+        let tree = HashMap::from([(20, (21, None)), (21, (21, Some(9)))]);
+        assert_eq!(find_app_id_in(&tree, &[20]), 9);
+    }
+
+    #[test]
+    fn app_id_is_zero_without_steam_game() {
+        let tree = HashMap::from([(30, (30, None)), (31, (30, Some(0)))]);
+        assert_eq!(find_app_id_in(&tree, &[30, 31]), 0);
+        assert_eq!(find_app_id_in(&tree, &[]), 0);
+    }
+
+    #[test]
+    fn app_id_errors_are_returned() {
+        let result = find_app_id(&[1], |_| None, |_| Err("lookup failed"));
+        assert_eq!(result, Err("lookup failed"));
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn blur_radius_round_trips() {
+        let xwayland = test_xwayland();
+        xwayland.set_blur_radius(7).unwrap();
+        assert_eq!(read_blur_radius(&xwayland).unwrap(), 7);
+        xwayland.set_blur_radius(0).unwrap();
+        assert_eq!(read_blur_radius(&xwayland).unwrap(), 0);
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn allow_tearing_round_trips() {
+        let xwayland = test_xwayland();
+        xwayland.set_allow_tearing(true).unwrap();
+        assert!(read_allow_tearing(&xwayland).unwrap());
+        xwayland.set_allow_tearing(false).unwrap();
+        assert!(!read_allow_tearing(&xwayland).unwrap());
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn hdr_enabled_writes_atom() {
+        let x11 = test_x11();
+        let root_window_id = x11.get_root_window_id();
+
+        write_hdr_enabled(&x11, true).unwrap();
+        let value = x11
+            .get_cardinal_property(root_window_id, "GAMESCOPE_DISPLAY_HDR_ENABLED")
+            .unwrap();
+        assert_eq!(value, Some(vec![1]));
+        assert!(read_hdr_enabled(&x11).unwrap());
+
+        write_hdr_enabled(&x11, false).unwrap();
+        let value = x11
+            .get_cardinal_property(root_window_id, "GAMESCOPE_DISPLAY_HDR_ENABLED")
+            .unwrap();
+        assert_eq!(value, Some(vec![0]));
+        assert!(!read_hdr_enabled(&x11).unwrap());
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn scaling_filter_round_trips() {
+        let x11 = test_x11();
+        for filter in (0..SCALING_FILTER_NAMES.len() as u32).rev() {
+            write_scaling_filter(&x11, filter).unwrap();
+            assert_eq!(read_scaling_filter(&x11).unwrap(), filter);
+        }
+    }
+
+    #[test]
+    fn only_one_reconnect_runs_at_a_time() {
+        let state = Arc::new(ReconnectState::new(RECONNECT_MAX_DELAY));
+
+        // Simulate many callers noticing the same disconnect at once
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                std::thread::spawn(move || (0..100).filter(|_| state.try_start()).count())
+            })
+            .collect();
+        let started: usize = handles.into_iter().map(|h| h.join().unwrap()).sum();
+        assert_eq!(started, 1);
+
+        // Another attempt is admitted once the running one succeeds
+        state.finish(true);
+        assert!(state.try_start());
+        assert!(!state.try_start());
+    }
+
+    #[test]
+    fn failed_reconnects_back_off() {
+        let max_delay = RECONNECT_MIN_DELAY * 4;
+        let state = ReconnectState::new(max_delay);
+
+        assert!(state.try_start());
+        state.finish(false);
+        // The next attempt must wait for the backoff to pass
+        assert!(!state.try_start());
+        assert_eq!(state.backoff.lock().unwrap().1, RECONNECT_MIN_DELAY * 2);
+
+        // The delay doubles on each failure up to the maximum
+        for _ in 0..4 {
+            state.backoff.lock().unwrap().0 = None;
+            assert!(state.try_start());
+            state.finish(false);
+        }
+        assert_eq!(state.backoff.lock().unwrap().1, max_delay);
+
+        // Succeeding resets the backoff
+        state.backoff.lock().unwrap().0 = None;
+        assert!(state.try_start());
+        state.finish(true);
+        assert_eq!(*state.backoff.lock().unwrap(), (None, RECONNECT_MIN_DELAY));
+        assert!(state.try_start());
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn scaling_mode_round_trips() {
+        let x11 = test_x11();
+        for mode in (0..SCALING_MODE_NAMES.len() as u32).rev() {
+            write_scaling_mode(&x11, mode).unwrap();
+            assert_eq!(read_scaling_mode(&x11).unwrap(), mode);
+        }
+    }
+
+    #[test]
+    #[ignore = "requires the X display named in GAMESCOPE_DBUS_TEST_DISPLAY"]
+    fn window_properties_keep_the_given_type() {
+        let x11 = test_x11();
+        let root_window_id = x11.get_root_window_id();
+        let name = "GAMESCOPE_DBUS_TEST_PROPERTY";
+
+        // Numeric strings stay strings
+        x11.set_property(root_window_id, name, "STRING", "1, 2")
+            .unwrap();
+        let value = x11.get_property(root_window_id, name).unwrap();
+        assert_eq!(value.as_deref(), Some("1, 2"));
+
+        x11.set_property(root_window_id, name, "CARDINAL", "1, 2")
+            .unwrap();
+        let value = x11.get_property(root_window_id, name).unwrap();
+        assert_eq!(value.as_deref(), Some("1,2"));
+
+        assert!(x11
+            .set_property(root_window_id, name, "CARDINAL", "one")
+            .is_err());
+        assert!(x11
+            .set_property(root_window_id, name, "WINDOW", "1")
+            .is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use simple_logger::SimpleLogger;
+
+/// Maximum number of log lines kept in memory. Older lines are dropped once
+/// this limit is reached.
+const MAX_LOG_LINES: usize = 1000;
+
+/// Most recent log lines, oldest first
+static RECENT_LOGS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Logger that forwards all records to [SimpleLogger] while also keeping the
+/// most recent log lines in memory so they can be retrieved over DBus.
+struct Logger {
+    inner: SimpleLogger,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        self.inner.log(record);
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let line = format!(
+            "{}.{:03} {:<5} [{}] {}",
+            timestamp.as_secs(),
+            timestamp.subsec_millis(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        let Ok(mut logs) = RECENT_LOGS.lock() else {
+            return;
+        };
+        if logs.len() >= MAX_LOG_LINES {
+            logs.pop_front();
+        }
+        logs.push_back(line);
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+/// Initializes the global logger
+pub fn init() -> Result<(), SetLoggerError> {
+    let inner = SimpleLogger::new();
+    log::set_max_level(inner.max_level());
+    log::set_boxed_logger(Box::new(Logger { inner }))
+}
+
+/// Sets the maximum level of messages that are logged. Accepts "trace",
+/// "debug", "info", "warn" or "error", in any case.
+pub fn set_level(level: &str) -> Result<(), String> {
+    let filter = level
+        .parse::<LevelFilter>()
+        .ok()
+        .filter(|filter| *filter != LevelFilter::Off)
+        .ok_or_else(|| {
+            format!("Invalid log level '{level}'. Expected trace, debug, info, warn or error")
+        })?;
+    log::set_max_level(filter);
+    Ok(())
+}
+
+/// Returns the maximum level of messages that are logged (E.g. "info")
+pub fn level() -> String {
+    log::max_level().as_str().to_lowercase()
+}
+
+/// Returns up to `max` of the most recent log lines, oldest first
+pub fn recent_logs(max: usize) -> Vec<String> {
+    let Ok(logs) = RECENT_LOGS.lock() else {
+        return Vec::new();
+    };
+    let skip = logs.len().saturating_sub(max);
+    logs.iter().skip(skip).cloned().collect()
+}
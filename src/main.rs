@@ -1,53 +1,144 @@
-use simple_logger::SimpleLogger;
-use tokio::signal;
+use std::{env, time::Duration};
+
+use tokio::{
+    signal::{
+        self,
+        unix::{signal as unix_signal, SignalKind},
+    },
+    sync::mpsc,
+};
 use zbus::{fdo::ObjectManager, Connection};
 
+use gamescope::manager::Command;
+
+mod args;
 mod gamescope;
+mod logger;
 pub mod utils;
 mod watcher;
 
+/// Maximum amount of time to wait for a graceful shutdown before exiting
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    SimpleLogger::new().init().unwrap();
+    logger::init().unwrap();
     const VERSION: &str = env!("CARGO_PKG_VERSION");
     log::info!("Starting Gamescope DBus v{}", VERSION);
+    let args = args::Args::parse();
+
+    if let Some(runtime_dir) = args.runtime_dir.clone() {
+        utils::set_run_user_dir(runtime_dir);
+    }
 
     // Configure the DBus connection
-    let connection = Connection::session().await?;
+    let connection = if args.system_bus {
+        log::info!("Connecting to the system bus");
+        Connection::system().await?
+    } else {
+        Connection::session().await?
+    };
 
     // Create an ObjectManager to signal when objects are added/removed
     let object_manager = ObjectManager {};
-    let object_manager_path = String::from("/org/shadowblip/Gamescope");
+    let object_manager_path = args.base_path.clone();
     connection
         .object_server()
         .at(object_manager_path, object_manager)
         .await?;
 
     // Create an instance of Gamescope Manager and its DBus interface
-    let mut manager = gamescope::manager::Manager::new(connection.clone());
-    let manager_dbus = gamescope::manager::DBusInterface::new();
+    let mut manager = gamescope::manager::Manager::new(connection.clone(), args.base_path.clone());
+    let manager_dbus = gamescope::manager::DBusInterface::new(manager.tx(), args.base_path.clone());
+    let manager_path = manager.manager_path();
+    let manager_tx = manager.tx();
+
+    // Wait before the initial discovery to give gamescope time to start
+    let startup_delay = env::var("GAMESCOPE_DBUS_STARTUP_DELAY_MS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(500);
+    tokio::time::sleep(Duration::from_millis(startup_delay)).await;
     manager.update_xwaylands().await?;
     manager.update_waylands().await?;
 
     // Serve the Gamescope Manager interace on DBus
-    let manager_path = String::from("/org/shadowblip/Gamescope/Manager");
     connection
         .object_server()
-        .at(manager_path, manager_dbus)
+        .at(manager_path.clone(), manager_dbus)
         .await?;
-    connection.request_name("org.shadowblip.Gamescope").await?;
+    connection.request_name(args.bus_name.as_str()).await?;
 
     // Listen for gamescope instance changes (added/removed)
     manager.watch_xwaylands().await?;
     manager.watch_waylands().await?;
 
+    // Periodically signal that the manager is alive
+    let heartbeat_interval = env::var("GAMESCOPE_DBUS_HEARTBEAT_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .unwrap_or(30);
+    manager.start_heartbeat(Duration::from_secs(heartbeat_interval));
+
     // Run the manager in its own thread
     tokio::spawn(async move {
         let _ = manager.run().await;
     });
 
-    signal::ctrl_c().await?;
+    // Wait for SIGINT or SIGTERM
+    let mut sigterm = unix_signal(SignalKind::terminate())?;
+    tokio::select! {
+        result = signal::ctrl_c() => result?,
+        _ = sigterm.recv() => (),
+    }
     log::info!("Terminating...");
+
+    let cleanup = shutdown(
+        &connection,
+        manager_tx,
+        &manager_path,
+        &args.base_path,
+        &args.bus_name,
+    );
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, cleanup)
+        .await
+        .is_err()
+    {
+        log::warn!("Timed out waiting for graceful shutdown");
+    }
     drop(connection);
     std::process::exit(0)
 }
+
+/// Removes all objects served by the daemon and releases the bus name
+async fn shutdown(
+    connection: &Connection,
+    manager_tx: mpsc::Sender<Command>,
+    manager_path: &str,
+    object_manager_path: &str,
+    bus_name: &str,
+) {
+    // Remove all XWayland and Wayland objects through the manager
+    let (sender, mut receiver) = mpsc::channel(1);
+    if manager_tx.send(Command::Shutdown { sender }).await.is_ok() {
+        receiver.recv().await;
+    }
+
+    let object_server = connection.object_server();
+    if let Err(e) = object_server
+        .remove::<gamescope::manager::DBusInterface, _>(manager_path)
+        .await
+    {
+        log::warn!("Failed to remove manager interface: {e}");
+    }
+    if let Err(e) = object_server
+        .remove::<ObjectManager, _>(object_manager_path)
+        .await
+    {
+        log::warn!("Failed to remove object manager: {e}");
+    }
+    if let Err(e) = connection.release_name(bus_name).await {
+        log::warn!("Failed to release bus name {bus_name}: {e}");
+    }
+    log::info!("Shutdown complete");
+}
@@ -1,8 +1,22 @@
-use std::env;
+use std::{env, sync::OnceLock};
 
 use nix::unistd::Uid;
 
+/// Explicit runtime directory that overrides XDG_RUNTIME_DIR when set
+static RUN_USER_DIR: OnceLock<String> = OnceLock::new();
+
+/// Overrides the directory returned by [get_run_user_dir]. Only the first
+/// call has any effect.
+pub fn set_run_user_dir(path: String) {
+    if RUN_USER_DIR.set(path).is_err() {
+        log::warn!("Runtime directory was already set");
+    }
+}
+
 pub fn get_run_user_dir() -> String {
+    if let Some(path) = RUN_USER_DIR.get() {
+        return path.clone();
+    }
     env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| format!("/run/user/{}", Uid::current()))
 }
 
@@ -1,6 +1,11 @@
+use std::{ffi::OsStr, io, thread, time::Duration};
+
 use inotify::{EventMask, Inotify, WatchMask};
 use tokio::sync::broadcast::Sender;
 
+/// Amount of time to wait before re-initializing a failed watch
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub enum WatchEvent {
@@ -19,69 +24,170 @@ pub enum WatchEvent {
         mask: EventMask,
         path: String,
     },
+    /// Events on the given path may have been missed, either because the
+    /// inotify queue overflowed or because the watch had to be re-initialized.
+    /// The path should be rescanned.
+    Overflow { path: String },
 }
 
 /// Watch for filesystem changes on the given path, sending [WatchEvent]
 /// to the given channel.
+/// If the watch fails, it is re-initialized and an [WatchEvent::Overflow] is
+/// sent so any events missed in the meantime can be recovered by a rescan.
 pub fn watch(path: String, tx: Sender<WatchEvent>) {
-    let mut inotify = Inotify::init().expect("Failed to initialize inotify");
+    let mut is_retry = false;
+    loop {
+        let mut inotify = match init_watch(&path) {
+            Ok(inotify) => inotify,
+            Err(e) => {
+                log::error!("Failed to initialize inotify watch on {}: {}", path, e);
+                thread::sleep(RETRY_DELAY);
+                continue;
+            }
+        };
+
+        if is_retry {
+            log::info!("Re-initialized inotify watch on {}", path);
+            send_overflow(&path, &tx);
+        }
 
-    inotify
-        .watches()
-        .add(path.clone(), WatchMask::CREATE | WatchMask::DELETE)
-        .expect("Failed to add inotify watch");
+        if let Err(e) = read_events(&mut inotify, &path, &tx) {
+            log::error!("Failed to read inotify events on {}: {}", path, e);
+        }
+        is_retry = true;
+        thread::sleep(RETRY_DELAY);
+    }
+}
 
+/// Creates a new inotify instance watching the given path
+fn init_watch(path: &str) -> io::Result<Inotify> {
+    let inotify = Inotify::init()?;
+    inotify.watches().add(
+        path,
+        WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MODIFY
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO,
+    )?;
+    Ok(inotify)
+}
+
+/// Sends a [WatchEvent::Overflow] for the given path
+fn send_overflow(path: &str, tx: &Sender<WatchEvent>) {
+    let value = WatchEvent::Overflow {
+        path: path.to_string(),
+    };
+    log::debug!("Sending overflow event");
+    if let Err(e) = tx.send(value) {
+        log::error!("Error sending event: {}", e)
+    }
+}
+
+/// Reads events from the given inotify instance and sends them to the given
+/// channel. Only returns if reading events fails.
+fn read_events(inotify: &mut Inotify, path: &str, tx: &Sender<WatchEvent>) -> io::Result<()> {
     // Listen for watch events
     let mut buffer = [0u8; 4096];
-    // Use the equivalent of a "do-while" loop so the initial value is
-    // processed before awaiting the `changed()` future.
     loop {
-        let events = inotify
-            .read_events_blocking(&mut buffer)
-            .expect("Failed to read inotify events");
+        let events = inotify.read_events_blocking(&mut buffer)?;
 
         for event in events {
+            log::debug!("inotify: {:?}", event.name);
+            let Some(value) = to_watch_event(event.mask, event.name, path) else {
+                continue;
+            };
+            if let WatchEvent::Overflow { .. } = value {
+                log::warn!("inotify queue overflowed while watching {}", path);
+            }
+
             // Send the event over our channel
-            log::debug!("inotify: {:?}", event.name.unwrap());
-            let name = String::from(event.name.unwrap().to_str().unwrap());
-
-            if event.mask.contains(EventMask::CREATE) {
-                let value = WatchEvent::Create {
-                    name,
-                    mask: event.mask,
-                    path: path.clone(),
-                };
-                log::debug!("Sending create event");
-                if let Err(e) = tx.send(value) {
-                    log::error!("Error sending event: {}", e)
-                }
-                log::debug!("Sent create event");
-                //if event.mask.contains(EventMask::ISDIR) {
-                //    println!("Directory created: {:?}", event.name);
-                //} else {
-                //    println!("File created: {:?}", event.name);
-                //}
-            } else if event.mask.contains(EventMask::DELETE) {
-                let value = WatchEvent::Delete {
-                    name,
-                    mask: event.mask,
-                    path: path.clone(),
-                };
-                log::debug!("Sending delete event");
-                if let Err(e) = tx.send(value) {
-                    log::error!("Error sending event: {}", e)
-                }
-            } else if event.mask.contains(EventMask::MODIFY) {
-                let value = WatchEvent::Modify {
-                    name,
-                    mask: event.mask,
-                    path: path.clone(),
-                };
-                log::debug!("Sending modify event");
-                if let Err(e) = tx.send(value) {
-                    log::error!("Error sending event: {}", e)
-                }
+            log::debug!("Sending event: {:?}", value);
+            if let Err(e) = tx.send(value) {
+                log::error!("Error sending event: {}", e)
             }
         }
     }
 }
+
+/// Converts an inotify event on the given path into a [WatchEvent]. Returns
+/// None for events that are not of interest.
+fn to_watch_event(mask: EventMask, name: Option<&OsStr>, path: &str) -> Option<WatchEvent> {
+    if mask.contains(EventMask::Q_OVERFLOW) {
+        return Some(WatchEvent::Overflow {
+            path: path.to_string(),
+        });
+    }
+
+    let name = String::from(name?.to_str()?);
+    let path = path.to_string();
+
+    // Files moved into the directory are treated as created, and files moved
+    // out of it are treated as deleted.
+    if mask.intersects(EventMask::CREATE | EventMask::MOVED_TO) {
+        Some(WatchEvent::Create { name, mask, path })
+    } else if mask.intersects(EventMask::DELETE | EventMask::MOVED_FROM) {
+        Some(WatchEvent::Delete { name, mask, path })
+    } else if mask.contains(EventMask::MODIFY) {
+        Some(WatchEvent::Modify { name, mask, path })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsStr;
+
+    use inotify::EventMask;
+
+    use super::{to_watch_event, WatchEvent};
+
+    const PATH: &str = "/run/user/1000";
+
+    #[test]
+    fn moved_to_is_create() {
+        let event = to_watch_event(EventMask::MOVED_TO, Some(OsStr::new("gamescope-0")), PATH);
+        assert!(matches!(
+            event,
+            Some(WatchEvent::Create { name, path, .. }) if name == "gamescope-0" && path == PATH
+        ));
+    }
+
+    #[test]
+    fn moved_from_is_delete() {
+        let event = to_watch_event(EventMask::MOVED_FROM, Some(OsStr::new("gamescope-0")), PATH);
+        assert!(matches!(
+            event,
+            Some(WatchEvent::Delete { name, path, .. }) if name == "gamescope-0" && path == PATH
+        ));
+    }
+
+    #[test]
+    fn queue_overflow_is_overflow() {
+        let event = to_watch_event(EventMask::Q_OVERFLOW, None, PATH);
+        assert!(matches!(event, Some(WatchEvent::Overflow { path }) if path == PATH));
+    }
+
+    #[test]
+    fn create_and_delete_are_mapped() {
+        let name = Some(OsStr::new("X1"));
+        assert!(matches!(
+            to_watch_event(EventMask::CREATE, name, PATH),
+            Some(WatchEvent::Create { .. })
+        ));
+        assert!(matches!(
+            to_watch_event(EventMask::DELETE, name, PATH),
+            Some(WatchEvent::Delete { .. })
+        ));
+        assert!(matches!(
+            to_watch_event(EventMask::MODIFY, name, PATH),
+            Some(WatchEvent::Modify { .. })
+        ));
+    }
+
+    #[test]
+    fn events_without_a_name_are_ignored() {
+        assert!(to_watch_event(EventMask::CREATE, None, PATH).is_none());
+    }
+}